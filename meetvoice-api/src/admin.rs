@@ -0,0 +1,645 @@
+//! Maintenance endpoints, all behind `require_admin`.
+
+use crate::{
+    bad_request, deserialize_lenient, i18n::Message, internal_error, list_projection, live,
+    normalize_slug, not_found, parse_date_param, publication_cutoff, timed, writes, ApiError,
+    AppState, Article, ArticleListItem, ListResponse, Page, PageQuery, Paginated,
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Bson, Document},
+    Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashSet, sync::atomic::Ordering};
+
+const DEFAULT_REINDEX_BATCH: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct ReindexQuery {
+    /// `next_after` of the previous call; omitted to start from the
+    /// beginning.
+    after: Option<ObjectId>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReindexResponse {
+    scanned: u64,
+    updated: u64,
+    /// Documents that don't decode as articles, logged and left untouched.
+    skipped: usize,
+    /// Pass back as `after` for the next batch; `None` once the whole
+    /// collection has been processed.
+    next_after: Option<ObjectId>,
+}
+
+/// Re-normalizes one batch of articles and recomputes their derived
+/// fields (see `writes::derive_fields`), in `_id` order; callers loop on
+/// `next_after` until it comes back `None`, so no single request has to
+/// cover the whole collection. Only documents that change are written, only
+/// those fields plus `updated_at`, so re-running is harmless. Documents
+/// that don't decode are logged and skipped rather than failing the batch.
+pub async fn reindex(
+    State(state): State<AppState>,
+    Query(params): Query<ReindexQuery>,
+) -> Result<Json<ReindexResponse>, ApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_REINDEX_BATCH).clamp(1, 5000);
+    let filter = match params.after {
+        Some(id) => doc! { "_id": { "$gt": id } },
+        None => doc! {},
+    };
+    let documents: Vec<Document> = timed(&state, "reindex", &filter, async {
+        state
+            .collection
+            .clone_with_type::<Document>()
+            .find(filter.clone())
+            .sort(doc! { "_id": 1 })
+            .limit(limit)
+            .max_time(state.config.long_timeout)
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+    // Resume past the whole batch, malformed documents included; a short
+    // batch means the end was reached.
+    let next_after = documents
+        .last()
+        .and_then(|d| d.get_object_id("_id").ok())
+        .filter(|_| documents.len() as i64 == limit);
+    let (batch, skipped) = deserialize_lenient::<Article>(documents, "reindex");
+
+    let mut scanned = 0;
+    let mut updated = 0;
+    for mut article in batch {
+        let Some(id) = article.id else { continue };
+        scanned += 1;
+        let before = derived_values(&article);
+        writes::derive_fields(&mut article, &state.config);
+        let after = derived_values(&article);
+        if before != after {
+            let filter = doc! { "_id": id };
+            let mut set = after;
+            set.insert("updated_at", writes::now_timestamp());
+            timed(
+                &state,
+                "reindex",
+                &filter,
+                state
+                    .collection
+                    .update_one(filter.clone(), doc! { "$set": set }),
+            )
+            .await
+            .map_err(internal_error)?;
+            updated += 1;
+        }
+    }
+    tracing::info!(scanned, updated, skipped, next_after = ?next_after, "Reindex batch");
+    if updated > 0 {
+        state.list_cache.clear();
+    }
+
+    Ok(Json(ReindexResponse {
+        scanned,
+        updated,
+        skipped,
+        next_after,
+    }))
+}
+
+fn derived_values(article: &Article) -> Document {
+    let full = bson::to_document(article).unwrap_or_default();
+    writes::DERIVED_FIELDS
+        .iter()
+        .chain(writes::NORMALIZED_FIELDS)
+        .filter_map(|field| full.get(*field).map(|v| (field.to_string(), v.clone())))
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateEntry {
+    #[serde(rename = "_id")]
+    id: ObjectId,
+    titre: Option<String>,
+    /// Set when the copy is soft-deleted; it still counts as a duplicate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateSlug {
+    slug: String,
+    count: u64,
+    articles: Vec<DuplicateEntry>,
+}
+
+/// Slugs used by more than one document, soft-deleted ones included, most
+/// repeated first. These must be resolved before a unique index on `slug`
+/// can be built. Read-only.
+pub async fn duplicate_slugs(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<DuplicateSlug>>, ApiError> {
+    let pipeline = vec![
+        doc! { "$group": {
+            "_id": "$slug",
+            "count": { "$sum": 1 },
+            "articles": { "$push": {
+                "_id": "$_id",
+                "titre": "$titre",
+                "deleted_at": "$deleted_at"
+            } }
+        } },
+        doc! { "$match": { "count": { "$gt": 1 } } },
+        doc! { "$sort": { "count": -1, "_id": 1 } },
+        doc! { "$project": { "_id": 0, "slug": "$_id", "count": 1, "articles": 1 } },
+    ];
+
+    let first_stage = pipeline[0].clone();
+    let duplicates = timed(&state, "duplicate_slugs", &first_stage, async {
+        state
+            .collection
+            .aggregate(pipeline)
+            .max_time(state.config.long_timeout)
+            .with_type::<DuplicateSlug>()
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(duplicates))
+}
+
+/// The stored document exactly as MongoDB has it, including fields the
+/// typed `Article` ignores, as relaxed extended JSON. Soft-deleted articles
+/// are included; with duplicate slugs, the oldest document is returned.
+pub async fn raw_article(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let slug = normalize_slug(&slug);
+    let filter = doc! { "slug": &slug };
+    let raw = timed(
+        &state,
+        "raw_article",
+        &filter,
+        state
+            .collection
+            .clone_with_type::<Document>()
+            .find_one(filter.clone())
+            .sort(doc! { "_id": 1 })
+            .max_time(state.config.long_timeout),
+    )
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| not_found(&slug))?;
+
+    Ok(Json(Bson::Document(raw).into_relaxed_extjson()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    /// `updated_at` to resume from, inclusive; `next_since` of the previous
+    /// page. Any RFC 3339 time or date works too.
+    since: Option<String>,
+    /// `next_after` of the previous page, so articles sharing the `since`
+    /// timestamp aren't returned twice.
+    after: Option<ObjectId>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArticleChange {
+    #[serde(flatten)]
+    article: Article,
+    deleted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangesResponse {
+    articles: Vec<ArticleChange>,
+    /// Pass both back as `since` and `after` for the next page; `None` when
+    /// the page is empty, in which case poll again with the same cursor.
+    next_since: Option<String>,
+    next_after: Option<ObjectId>,
+}
+
+/// Every article changed at or after `since`, drafts and soft-deleted ones
+/// included, oldest change first, for consumers catching up on missed
+/// events. Bulk writes stamp many articles with the same `updated_at`, so
+/// the cursor is `(updated_at, _id)`. Articles never written since
+/// `updated_at` existed don't appear.
+pub async fn article_changes(
+    State(state): State<AppState>,
+    Query(params): Query<ChangesQuery>,
+) -> Result<Json<ChangesResponse>, ApiError> {
+    let limit = params
+        .limit
+        .unwrap_or(state.config.max_batch_size as i64)
+        .clamp(1, state.config.max_batch_size as i64);
+    let since = params
+        .since
+        .as_deref()
+        .map(|s| stored_timestamp("since", s))
+        .transpose()?;
+    let filter = match (since, params.after) {
+        (Some(since), Some(after)) => doc! { "$or": [
+            { "updated_at": { "$gt": &since } },
+            { "updated_at": &since, "_id": { "$gt": after } },
+        ] },
+        (Some(since), None) => doc! { "updated_at": { "$gte": since } },
+        (None, _) => doc! { "updated_at": { "$ne": null } },
+    };
+
+    let articles: Vec<Article> = timed(&state, "article_changes", &filter, async {
+        state
+            .collection
+            .find(filter.clone())
+            .sort(doc! { "updated_at": 1, "_id": 1 })
+            .limit(limit)
+            .max_time(state.config.long_timeout)
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    let last = articles.last();
+    let next_since = last.and_then(|a| a.updated_at.clone());
+    let next_after = last.and_then(|a| a.id);
+    Ok(Json(ChangesResponse {
+        next_since,
+        next_after,
+        articles: articles
+            .into_iter()
+            .map(|article| ArticleChange {
+                deleted: article.deleted_at.is_some(),
+                article,
+            })
+            .collect(),
+    }))
+}
+
+/// A `since` value in the fixed `writes::now_timestamp` format, so it
+/// compares correctly with stored `updated_at` strings.
+fn stored_timestamp(param: &'static str, value: &str) -> Result<String, ApiError> {
+    let parsed = parse_date_param(param, value)?;
+    let utc = match DateTime::parse_from_rfc3339(value.trim()) {
+        Ok(exact) => exact.with_timezone(&Utc),
+        Err(_) => NaiveDate::parse_from_str(&parsed, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| d.and_utc())
+            .unwrap_or_default(),
+    };
+    Ok(utc.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+}
+
+/// The "coming up" queue: articles dated after now, which `published`
+/// hides, soonest first. Drafts stay out since they won't go live on their
+/// date.
+pub async fn scheduled_articles(
+    State(state): State<AppState>,
+    Query(params): Query<PageQuery>,
+) -> Result<Json<ListResponse>, ApiError> {
+    let paging = Page::new(params.page, params.limit, &state.config)?;
+    let filter = live(doc! {
+        "status": { "$ne": "draft" },
+        "date_publication": { "$gt": publication_cutoff() },
+    });
+
+    let total = timed(
+        &state,
+        "scheduled_articles",
+        &filter,
+        state
+            .collection
+            .count_documents(filter.clone())
+            .max_time(state.config.long_timeout),
+    )
+    .await
+    .map_err(internal_error)?;
+
+    let articles: Vec<Article> = timed(&state, "scheduled_articles", &filter, async {
+        state
+            .collection
+            .find(filter.clone())
+            .max_time(state.config.long_timeout)
+            .sort(doc! { "date_publication": 1, "_id": 1 })
+            .skip(paging.skip)
+            .limit(paging.limit)
+            .projection(list_projection())
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(Paginated::new(
+        articles.into_iter().map(ArticleListItem::from).collect(),
+        total,
+        paging,
+    )))
+}
+
+/// Articles scanned per lookup of the slugs they reference.
+const ORPHAN_SCAN_BATCH: usize = 500;
+
+#[derive(Debug, Deserialize)]
+struct RelatedRefs {
+    #[serde(rename = "_id")]
+    id: ObjectId,
+    slug: String,
+    #[serde(default)]
+    related: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrphanedRef {
+    #[serde(skip)]
+    id: ObjectId,
+    slug: String,
+    /// Related slugs with no live article behind them.
+    missing: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrphanedRefsResponse {
+    /// Live articles with at least one related slug.
+    scanned: u64,
+    articles: Vec<OrphanedRef>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CleanOrphanedRefsResponse {
+    articles: u64,
+    removed: u64,
+}
+
+/// Live articles whose `related` list names a slug that doesn't exist or
+/// is soft-deleted. Read-only; see `clean_orphaned_refs`.
+pub async fn orphaned_refs(
+    State(state): State<AppState>,
+) -> Result<Json<OrphanedRefsResponse>, ApiError> {
+    scan_orphaned_refs(&state).await.map(Json)
+}
+
+/// Strips the dangling slugs found by `orphaned_refs` from each article's
+/// `related` list.
+pub async fn clean_orphaned_refs(
+    State(state): State<AppState>,
+) -> Result<Json<CleanOrphanedRefsResponse>, ApiError> {
+    let report = scan_orphaned_refs(&state).await?;
+    let mut cleaned = CleanOrphanedRefsResponse {
+        articles: 0,
+        removed: 0,
+    };
+    for orphan in report.articles {
+        let filter = doc! { "_id": orphan.id };
+        let update = doc! {
+            "$pull": { "related": { "$in": &orphan.missing } },
+            "$set": { "updated_at": writes::now_timestamp() },
+        };
+        let result = timed(
+            &state,
+            "clean_orphaned_refs",
+            &filter,
+            state.collection.update_one(filter.clone(), update),
+        )
+        .await
+        .map_err(internal_error)?;
+        if result.modified_count > 0 {
+            cleaned.articles += 1;
+            cleaned.removed += orphan.missing.len() as u64;
+        }
+    }
+    tracing::info!(
+        articles = cleaned.articles,
+        removed = cleaned.removed,
+        "Removed orphaned related references"
+    );
+    Ok(Json(cleaned))
+}
+
+/// Walks the articles with related slugs in batches of `ORPHAN_SCAN_BATCH`,
+/// resolving each batch's slugs in one query, so only the orphans found
+/// are held in memory.
+async fn scan_orphaned_refs(state: &AppState) -> Result<OrphanedRefsResponse, ApiError> {
+    let filter = live(doc! { "related.0": { "$exists": true } });
+    let mut cursor = timed(
+        state,
+        "orphaned_refs",
+        &filter,
+        state
+            .collection
+            .clone_with_type::<RelatedRefs>()
+            .find(filter.clone())
+            .projection(doc! { "slug": 1, "related": 1 })
+            .sort(doc! { "_id": 1 })
+            .max_time(state.config.long_timeout),
+    )
+    .await
+    .map_err(internal_error)?;
+
+    let mut report = OrphanedRefsResponse {
+        scanned: 0,
+        articles: Vec::new(),
+    };
+    let mut batch = Vec::with_capacity(ORPHAN_SCAN_BATCH);
+    while let Some(article) = cursor.try_next().await.map_err(internal_error)? {
+        report.scanned += 1;
+        batch.push(article);
+        if batch.len() == ORPHAN_SCAN_BATCH {
+            let full = std::mem::take(&mut batch);
+            report.articles.extend(dangling_refs(state, full).await?);
+        }
+    }
+    report.articles.extend(dangling_refs(state, batch).await?);
+    Ok(report)
+}
+
+async fn dangling_refs(
+    state: &AppState,
+    batch: Vec<RelatedRefs>,
+) -> Result<Vec<OrphanedRef>, ApiError> {
+    if batch.is_empty() {
+        return Ok(Vec::new());
+    }
+    let wanted: HashSet<&str> = batch
+        .iter()
+        .flat_map(|a| a.related.iter().map(String::as_str))
+        .collect();
+    let filter = live(doc! { "slug": { "$in": wanted.into_iter().collect::<Vec<_>>() } });
+    let existing: HashSet<String> = timed(
+        state,
+        "orphaned_refs",
+        &filter,
+        state
+            .collection
+            .distinct("slug", filter.clone())
+            .max_time(state.config.long_timeout),
+    )
+    .await
+    .map_err(internal_error)?
+    .into_iter()
+    .filter_map(|slug| slug.as_str().map(str::to_string))
+    .collect();
+
+    Ok(batch
+        .into_iter()
+        .filter_map(|article| {
+            let missing: Vec<String> = article
+                .related
+                .into_iter()
+                .filter(|slug| !existing.contains(slug))
+                .collect();
+            (!missing.is_empty()).then_some(OrphanedRef {
+                id: article.id,
+                slug: article.slug,
+                missing,
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeRequest {
+    keep: String,
+    remove: String,
+}
+
+/// Fields the kept article never takes from the removed one: identity,
+/// bookkeeping, and the lists `merge_into` combines itself.
+const MERGE_SKIPPED_FIELDS: &[&str] = &[
+    "_id",
+    "slug",
+    "status",
+    "sort_weight",
+    "updated_at",
+    "deleted_at",
+    "auto_seo",
+    "old_slugs",
+    "related",
+];
+
+/// Folds the `remove` article into `keep`: fields blank in `keep` are
+/// filled from `remove`, `remove`'s slug joins `keep`'s `old_slugs`, and
+/// `remove` is soft-deleted. Returns the merged article.
+pub async fn merge_articles(
+    State(state): State<AppState>,
+    Json(request): Json<MergeRequest>,
+) -> Result<Json<Article>, ApiError> {
+    let keep = normalize_slug(&request.keep);
+    let remove = normalize_slug(&request.remove);
+    if keep == remove {
+        return Err(bad_request(Message::ConflictingParams("keep", "remove")));
+    }
+    let kept = writes::find_article(&state, &keep).await?;
+    let removed = writes::find_article(&state, &remove).await?;
+
+    let mut merged = merge_into(&kept, &removed).map_err(internal_error)?;
+    merged.updated_at = Some(writes::now_timestamp());
+    writes::replace_if_unchanged(&state, &kept, &mut merged).await?;
+
+    // After the kept article is saved, so a failure here leaves both live
+    // and the merge can simply be retried.
+    let filter = live(doc! { "_id": removed.id });
+    let now = writes::now_timestamp();
+    let update = doc! { "$set": { "deleted_at": &now, "updated_at": &now } };
+    timed(
+        &state,
+        "merge_articles",
+        &filter,
+        state.collection.update_one(filter.clone(), update),
+    )
+    .await
+    .map_err(internal_error)?;
+
+    state.list_cache.invalidate(&kept);
+    state.list_cache.invalidate(&merged);
+    state.list_cache.invalidate(&removed);
+    tracing::info!(keep, remove, "Merged articles");
+    Ok(Json(merged))
+}
+
+fn merge_into(kept: &Article, removed: &Article) -> mongodb::error::Result<Article> {
+    let is_blank = |value: &Bson| match value {
+        Bson::Null => true,
+        Bson::String(s) => s.trim().is_empty(),
+        Bson::Array(items) => items.is_empty(),
+        _ => false,
+    };
+    let mut fields = bson::to_document(kept)?;
+    for (key, value) in bson::to_document(removed)? {
+        if MERGE_SKIPPED_FIELDS.contains(&key.as_str()) || is_blank(&value) {
+            continue;
+        }
+        if fields.get(&key).is_none_or(is_blank) {
+            fields.insert(key, value);
+        }
+    }
+    let mut merged: Article = bson::from_document(fields)?;
+
+    let mut old_slugs = merged.old_slugs.take().unwrap_or_default();
+    let removed_slugs = std::iter::once(&removed.slug).chain(removed.old_slugs.iter().flatten());
+    for slug in removed_slugs {
+        if *slug != merged.slug && !old_slugs.contains(slug) {
+            old_slugs.push(slug.clone());
+        }
+    }
+    merged.old_slugs = Some(old_slugs);
+
+    // The merged article can't list itself, under either slug.
+    let mut related: Vec<String> = Vec::new();
+    for slug in kept.related.iter().chain(removed.related.iter()).flatten() {
+        if *slug != kept.slug && *slug != removed.slug && !related.contains(slug) {
+            related.push(slug.clone());
+        }
+    }
+    related.truncate(writes::MAX_RELATED);
+    merged.related = (!related.is_empty()).then_some(related);
+    Ok(merged)
+}
+
+pub async fn ensure_indexes(collection: &Collection<Article>) -> mongodb::error::Result<()> {
+    let index = IndexModel::builder()
+        .keys(doc! { "updated_at": 1, "_id": 1 })
+        .build();
+    collection.create_index(index).await?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    enabled: bool,
+}
+
+pub async fn maintenance_status(State(state): State<AppState>) -> Json<MaintenanceState> {
+    Json(MaintenanceState {
+        enabled: state.maintenance.load(Ordering::Relaxed),
+    })
+}
+
+/// Turns maintenance mode on or off for this instance until the next
+/// restart, which goes back to `MAINTENANCE_MODE`.
+pub async fn set_maintenance(
+    State(state): State<AppState>,
+    Json(request): Json<MaintenanceState>,
+) -> Json<MaintenanceState> {
+    let was = state.maintenance.swap(request.enabled, Ordering::Relaxed);
+    if was != request.enabled {
+        tracing::warn!(enabled = request.enabled, "Maintenance mode toggled");
+    }
+    Json(request)
+}
@@ -0,0 +1,248 @@
+//! Short-lived cache of category and theme listings, the hot landing-page
+//! queries. Only `list_articles` calls filtered by `categorie` or `theme`
+//! are cached. Writes evict the entries the written article could appear
+//! in; the TTL bounds staleness for everything else, such as scheduled
+//! articles going live.
+
+use crate::{Article, ListResponse};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// The listing filters an entry was computed for, to decide which writes
+/// evict it.
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub categorie: Option<String>,
+    pub theme: Option<String>,
+}
+
+impl Scope {
+    /// Whether `article` could be part of the listing. Themes are matched
+    /// as patterns by the listing, so any article with a theme counts.
+    fn covers(&self, article: &Article) -> bool {
+        let categorie = match &self.categorie {
+            Some(c) => article.categorie.as_ref() == Some(c),
+            None => true,
+        };
+        let theme = self.theme.is_none() || article.theme.is_some();
+        categorie && theme
+    }
+}
+
+struct Entry {
+    response: ListResponse,
+    /// `collection_fingerprint` when the page was read, so hits can answer
+    /// `If-None-Match` without querying.
+    fingerprint: String,
+    scope: Scope,
+    stored: Instant,
+    /// Insertion order, to pick the oldest entry without comparing clock
+    /// readings that may tie.
+    sequence: u64,
+}
+
+pub struct ListCache {
+    ttl: Duration,
+    /// `0` disables the cache.
+    capacity: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+    inserted: AtomicU64,
+}
+
+impl ListCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        ListCache {
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+            inserted: AtomicU64::new(0),
+        }
+    }
+
+    /// The page and the collection fingerprint it was read at.
+    pub fn get(&self, key: &str) -> Option<(ListResponse, String)> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(key)
+            .filter(|e| e.stored.elapsed() < self.ttl)
+            .map(|e| (e.response.clone(), e.fingerprint.clone()))
+    }
+
+    /// Stores a response, making room by dropping expired entries and then
+    /// the oldest one.
+    pub fn insert(&self, key: String, scope: Scope, response: ListResponse, fingerprint: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            entries.retain(|_, e| e.stored.elapsed() < self.ttl);
+        }
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, e)| e.sequence)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            Entry {
+                response,
+                fingerprint,
+                scope,
+                stored: Instant::now(),
+                sequence: self.inserted.fetch_add(1, Ordering::Relaxed),
+            },
+        );
+    }
+
+    /// Evicts every listing `article` could appear in. Call it with both
+    /// the old and the new version of an edited article.
+    pub fn invalidate(&self, article: &Article) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, e| !e.scope.covers(article));
+    }
+
+    /// For writes matching an arbitrary set of articles.
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Page, Paginated};
+
+    fn page(total: u64) -> ListResponse {
+        let paging = Page {
+            page: 1,
+            limit: 10,
+            skip: 0,
+        };
+        Paginated::new(Vec::new(), total, paging)
+    }
+
+    fn article(categorie: Option<&str>, theme: Option<&str>) -> Article {
+        serde_json::from_value(serde_json::json!({
+            "slug": "a",
+            "titre": "A",
+            "categorie": categorie,
+            "theme": theme,
+        }))
+        .unwrap()
+    }
+
+    fn scope(categorie: Option<&str>, theme: Option<&str>) -> Scope {
+        Scope {
+            categorie: categorie.map(str::to_string),
+            theme: theme.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn hit_returns_the_stored_page_and_fingerprint() {
+        let cache = ListCache::new(Duration::from_secs(60), 8);
+        cache.insert("k".into(), scope(Some("tech"), None), page(3), "fp".into());
+        let (response, fingerprint) = cache.get("k").unwrap();
+        assert_eq!(response.total, 3);
+        assert_eq!(fingerprint, "fp");
+        assert!(cache.get("other").is_none());
+    }
+
+    #[test]
+    fn expired_entries_miss() {
+        let cache = ListCache::new(Duration::ZERO, 8);
+        cache.insert("k".into(), scope(Some("tech"), None), page(1), "fp".into());
+        assert!(cache.get("k").is_none());
+    }
+
+    #[test]
+    fn zero_capacity_disables_the_cache() {
+        let cache = ListCache::new(Duration::from_secs(60), 0);
+        cache.insert("k".into(), scope(Some("tech"), None), page(1), "fp".into());
+        assert!(cache.get("k").is_none());
+    }
+
+    #[test]
+    fn full_cache_drops_the_oldest_entry() {
+        let cache = ListCache::new(Duration::from_secs(60), 2);
+        for key in ["a", "b", "c"] {
+            cache.insert(key.into(), scope(Some("tech"), None), page(1), "fp".into());
+        }
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn write_evicts_only_listings_the_article_could_appear_in() {
+        let cache = ListCache::new(Duration::from_secs(60), 8);
+        cache.insert(
+            "tech".into(),
+            scope(Some("tech"), None),
+            page(1),
+            "fp".into(),
+        );
+        cache.insert(
+            "food".into(),
+            scope(Some("food"), None),
+            page(1),
+            "fp".into(),
+        );
+        cache.insert(
+            "themed".into(),
+            scope(None, Some("ia")),
+            page(1),
+            "fp".into(),
+        );
+
+        cache.invalidate(&article(Some("tech"), None));
+        assert!(cache.get("tech").is_none());
+        assert!(cache.get("food").is_some());
+        assert!(cache.get("themed").is_some());
+
+        cache.invalidate(&article(Some("food"), Some("cuisine")));
+        assert!(cache.get("food").is_none());
+        assert!(cache.get("themed").is_none());
+    }
+
+    #[test]
+    fn clear_evicts_everything() {
+        let cache = ListCache::new(Duration::from_secs(60), 8);
+        cache.insert("a".into(), scope(Some("tech"), None), page(1), "fp".into());
+        cache.insert("b".into(), scope(None, Some("ia")), page(1), "fp".into());
+        cache.clear();
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_none());
+    }
+
+    #[test]
+    fn scope_covers() {
+        let tech = scope(Some("tech"), None);
+        assert!(tech.covers(&article(Some("tech"), None)));
+        assert!(!tech.covers(&article(Some("food"), None)));
+        assert!(!tech.covers(&article(None, None)));
+
+        // Themes are patterns, so any themed article may match.
+        let themed = scope(None, Some("ia"));
+        assert!(themed.covers(&article(None, Some("cuisine"))));
+        assert!(!themed.covers(&article(Some("tech"), None)));
+
+        let both = scope(Some("tech"), Some("ia"));
+        assert!(both.covers(&article(Some("tech"), Some("x"))));
+        assert!(!both.covers(&article(Some("tech"), None)));
+    }
+}
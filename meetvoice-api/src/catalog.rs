@@ -0,0 +1,433 @@
+//! Catalog-wide aggregations.
+
+use crate::{
+    bad_request, date_range, i18n::Message, internal_error, parse_date_param, published, timed,
+    ApiError, AppState, ArticleListItem, LIST_FIELDS, TRUNCATED_HEADER,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderValue,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Bson, DateTime, Document};
+use serde::{Deserialize, Serialize};
+
+/// Aggregation results cut at `MAX_AGGREGATION_RESULTS`. Still served as
+/// a bare array; when anything was dropped the response carries
+/// `X-Truncated: true`, like the paging headers of `envelope=false`.
+pub struct Capped<T> {
+    items: Vec<T>,
+    truncated: bool,
+}
+
+impl<T> Capped<T> {
+    /// `items` from a pipeline ending in `cap_stage`, which fetches one
+    /// result past the cap so the cut can be detected.
+    fn new(mut items: Vec<T>, cap: usize) -> Self {
+        let truncated = items.len() > cap;
+        items.truncate(cap);
+        Capped { items, truncated }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Capped<T> {
+    fn into_response(self) -> Response {
+        let mut response = Json(self.items).into_response();
+        if self.truncated {
+            response
+                .headers_mut()
+                .insert(TRUNCATED_HEADER, HeaderValue::from_static("true"));
+        }
+        response
+    }
+}
+
+fn cap_stage(cap: usize) -> Document {
+    doc! { "$limit": cap as i64 + 1 }
+}
+
+const DEFAULT_KEYWORDS: i64 = 20;
+const MAX_KEYWORDS: i64 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct KeywordsQuery {
+    limit: Option<i64>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeywordCount {
+    keyword: String,
+    count: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Day,
+    Week,
+    #[default]
+    Month,
+}
+
+impl Granularity {
+    fn unit(self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+        }
+    }
+
+    /// `2024-03-05`, `2024-W10` (ISO week) or `2024-03`.
+    fn label(self, date: NaiveDate) -> String {
+        match self {
+            Granularity::Day => date.format("%Y-%m-%d").to_string(),
+            Granularity::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Granularity::Month => date.format("%Y-%m").to_string(),
+        }
+    }
+
+    fn next(self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Granularity::Day => date.checked_add_days(Days::new(1)),
+            Granularity::Week => date.checked_add_days(Days::new(7)),
+            Granularity::Month => date.checked_add_months(Months::new(1)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimelineQuery {
+    #[serde(default)]
+    granularity: Granularity,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeriodCount {
+    #[serde(rename = "_id")]
+    start: DateTime,
+    count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimelinePoint {
+    period: String,
+    count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryOverview {
+    categorie: String,
+    count: u64,
+    /// Most recent articles first, at most `CATEGORY_PREVIEW_COUNT`.
+    articles: Vec<ArticleListItem>,
+}
+
+/// `{field: "$field"}` for every `LIST_FIELDS` entry, to collect list items
+/// inside a `$group`.
+fn list_item_fields() -> Document {
+    LIST_FIELDS
+        .iter()
+        .map(|f| (f.to_string(), Bson::String(format!("${}", f))))
+        .collect()
+}
+
+/// Every category with its article count and latest articles, largest
+/// categories first, in a single aggregation.
+pub async fn categories_overview(
+    State(state): State<AppState>,
+) -> Result<Capped<CategoryOverview>, ApiError> {
+    let preview = state.config.category_preview_count;
+    let cap = state.config.max_aggregation_results;
+    let pipeline = vec![
+        doc! { "$match": published(doc! { "categorie": { "$nin": [null, ""] } }) },
+        doc! { "$sort": { "date_publication": -1, "_id": -1 } },
+        doc! { "$group": {
+            "_id": "$categorie",
+            "count": { "$sum": 1 },
+            "articles": { "$push": list_item_fields() }
+        } },
+        doc! { "$project": {
+            "_id": 0,
+            "categorie": "$_id",
+            "count": 1,
+            "articles": { "$slice": ["$articles", preview] }
+        } },
+        doc! { "$sort": { "count": -1, "categorie": 1 } },
+        cap_stage(cap),
+    ];
+
+    let first_stage = pipeline[0].clone();
+    let overview = timed(&state, "categories_overview", &first_stage, async {
+        state
+            .analytics
+            .aggregate(pipeline)
+            .max_time(state.config.read_timeout)
+            .with_type::<CategoryOverview>()
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Capped::new(overview, cap))
+}
+
+/// Most used SEO keywords, most frequent first and alphabetically among
+/// ties. Keywords are compared case-insensitively and reported lowercased;
+/// articles without keywords don't count.
+pub async fn top_keywords(
+    State(state): State<AppState>,
+    Query(params): Query<KeywordsQuery>,
+) -> Result<Capped<KeywordCount>, ApiError> {
+    let cap = state.config.max_aggregation_results;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_KEYWORDS)
+        .clamp(1, MAX_KEYWORDS);
+    let from = params
+        .date_from
+        .as_deref()
+        .map(|d| parse_date_param("date_from", d))
+        .transpose()?;
+
+    let mut matcher = published(doc! { "seo_keywords.0": { "$exists": true } });
+    if let Some(range) = date_range(from, params.date_to.as_deref())? {
+        matcher.insert("date_publication", range);
+    }
+
+    let pipeline = vec![
+        doc! { "$match": matcher },
+        doc! { "$unwind": "$seo_keywords" },
+        doc! { "$group": {
+            "_id": { "$toLower": { "$trim": { "input": "$seo_keywords" } } },
+            "count": { "$sum": 1 }
+        } },
+        doc! { "$match": { "_id": { "$ne": "" } } },
+        doc! { "$sort": { "count": -1, "_id": 1 } },
+        doc! { "$limit": limit },
+        doc! { "$project": { "_id": 0, "keyword": "$_id", "count": 1 } },
+        cap_stage(cap),
+    ];
+
+    let first_stage = pipeline[0].clone();
+    let keywords = timed(&state, "top_keywords", &first_stage, async {
+        state
+            .analytics
+            .aggregate(pipeline)
+            .max_time(state.config.read_timeout)
+            .with_type::<KeywordCount>()
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Capped::new(keywords, cap))
+}
+
+/// Fields `GET /facets/:field` may group by, and whether each holds an
+/// array whose elements count separately. Adding a facet is one entry.
+const FACET_FIELDS: &[(&str, bool)] = &[
+    ("categorie", false),
+    ("theme", false),
+    ("author", false),
+    ("tags", true),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FacetValue {
+    value: String,
+    count: u64,
+}
+
+/// Distinct values of an allowlisted field across published articles with
+/// how many articles use each, most used first and alphabetically among
+/// ties. Missing and empty values are left out.
+pub async fn facet_values(
+    State(state): State<AppState>,
+    Path(field): Path<String>,
+) -> Result<Capped<FacetValue>, ApiError> {
+    let Some(&(field, is_array)) = FACET_FIELDS.iter().find(|(f, _)| *f == field) else {
+        return Err(bad_request(Message::UnknownFacet {
+            field,
+            allowed: FACET_FIELDS
+                .iter()
+                .map(|(f, _)| *f)
+                .collect::<Vec<_>>()
+                .join(", "),
+        }));
+    };
+    let cap = state.config.max_aggregation_results;
+    let path = format!("${}", field);
+
+    let mut pipeline = vec![doc! { "$match": published(doc! {}) }];
+    if is_array {
+        pipeline.push(doc! { "$unwind": &path });
+    }
+    pipeline.extend([
+        doc! { "$match": { field: { "$type": "string", "$ne": "" } } },
+        doc! { "$group": { "_id": &path, "count": { "$sum": 1 } } },
+        doc! { "$sort": { "count": -1, "_id": 1 } },
+        doc! { "$project": { "_id": 0, "value": "$_id", "count": 1 } },
+        cap_stage(cap),
+    ]);
+
+    let first_stage = pipeline[0].clone();
+    let values = timed(&state, "facet_values", &first_stage, async {
+        state
+            .analytics
+            .aggregate(pipeline)
+            .max_time(state.config.read_timeout)
+            .with_type::<FacetValue>()
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Capped::new(values, cap))
+}
+
+/// Articles published per period, oldest first. Periods without articles
+/// between the first and last publication are included with a zero count.
+/// Articles whose `date_publication` doesn't parse as a date are ignored.
+/// The cap applies to the filled series, so the oldest periods are kept.
+pub async fn publication_timeline(
+    State(state): State<AppState>,
+    Query(params): Query<TimelineQuery>,
+) -> Result<Capped<TimelinePoint>, ApiError> {
+    let granularity = params.granularity;
+    let cap = state.config.max_aggregation_results;
+    let pipeline = vec![
+        doc! { "$match": published(doc! { "date_publication": { "$nin": [null, ""] } }) },
+        doc! { "$project": { "published": { "$dateFromString": {
+            "dateString": "$date_publication",
+            "onError": null,
+            "onNull": null
+        } } } },
+        doc! { "$match": { "published": { "$ne": null } } },
+        doc! { "$group": {
+            "_id": { "$dateTrunc": {
+                "date": "$published",
+                "unit": granularity.unit(),
+                "startOfWeek": "monday"
+            } },
+            "count": { "$sum": 1 }
+        } },
+        doc! { "$sort": { "_id": 1 } },
+        cap_stage(cap),
+    ];
+
+    let first_stage = pipeline[0].clone();
+    let periods: Vec<PeriodCount> = timed(&state, "publication_timeline", &first_stage, async {
+        state
+            .analytics
+            .aggregate(pipeline)
+            .max_time(state.config.read_timeout)
+            .with_type::<PeriodCount>()
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    // One point past the cap, so `Capped` can tell the series was cut.
+    let points = fill_gaps(granularity, &periods, cap + 1);
+    Ok(Capped::new(points, cap))
+}
+
+/// At most `limit` points from the first period on; a few articles years
+/// apart would otherwise fill every day between them before the cap.
+fn fill_gaps(
+    granularity: Granularity,
+    periods: &[PeriodCount],
+    limit: usize,
+) -> Vec<TimelinePoint> {
+    let to_date = |d: &DateTime| {
+        chrono::DateTime::from_timestamp_millis(d.timestamp_millis())
+            .unwrap_or_default()
+            .date_naive()
+    };
+    let (Some(first), Some(last)) = (periods.first(), periods.last()) else {
+        return Vec::new();
+    };
+
+    let mut points = Vec::new();
+    let mut counts = periods.iter().peekable();
+    let mut current = Some(to_date(&first.start));
+    let end = to_date(&last.start);
+    while let Some(date) = current.filter(|d| *d <= end && points.len() < limit) {
+        let count = match counts.peek() {
+            Some(p) if to_date(&p.start) == date => counts.next().map_or(0, |p| p.count),
+            _ => 0,
+        };
+        points.push(TimelinePoint {
+            period: granularity.label(date),
+            count,
+        });
+        current = granularity.next(date);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn period(date: &str, count: u64) -> PeriodCount {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        let millis = date
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis();
+        PeriodCount {
+            start: DateTime::from_millis(millis),
+            count,
+        }
+    }
+
+    fn labels(points: &[TimelinePoint]) -> Vec<(&str, u64)> {
+        points
+            .iter()
+            .map(|p| (p.period.as_str(), p.count))
+            .collect()
+    }
+
+    #[test]
+    fn fill_gaps_inserts_empty_periods() {
+        let periods = [period("2024-01-01", 2), period("2024-04-01", 1)];
+        let points = fill_gaps(Granularity::Month, &periods, 100);
+        assert_eq!(
+            labels(&points),
+            [
+                ("2024-01", 2),
+                ("2024-02", 0),
+                ("2024-03", 0),
+                ("2024-04", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn fill_gaps_stops_at_the_limit() {
+        let periods = [period("2000-01-01", 1), period("2024-01-01", 1)];
+        let points = fill_gaps(Granularity::Day, &periods, 3);
+        assert_eq!(
+            labels(&points),
+            [("2000-01-01", 1), ("2000-01-02", 0), ("2000-01-03", 0)]
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! CSV export of the catalog, for migrations and spreadsheets.
+
+use crate::{bad_request, i18n::Message, internal_error, live, timed, ApiError, AppState, Article};
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures::{stream, StreamExt};
+use mongodb::bson::doc;
+use serde::Deserialize;
+
+/// Columns `fields` may name, in the default order.
+const EXPORT_FIELDS: &[&str] = &[
+    "slug",
+    "titre",
+    "categorie",
+    "theme",
+    "author",
+    "tags",
+    "status",
+    "date_publication",
+    "updated_at",
+    "petit_description",
+    "photo",
+    "photo_description",
+    "seo_title",
+    "seo_description",
+    "seo_keywords",
+    "series",
+    "series_order",
+    "related",
+    "contenu",
+];
+
+/// Columns when `fields` is omitted: the metadata, without the bodies.
+const DEFAULT_EXPORT_FIELDS: &[&str] = &[
+    "slug",
+    "titre",
+    "categorie",
+    "theme",
+    "author",
+    "tags",
+    "status",
+    "date_publication",
+    "updated_at",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// Comma-separated columns from `EXPORT_FIELDS`, in the order wanted.
+    fields: Option<String>,
+}
+
+/// Every live article, drafts included, as CSV (RFC 4180), oldest first.
+/// Rows are written as the cursor yields them, so large bodies are never
+/// all held in memory. List fields are joined with `|`.
+pub async fn export_csv(
+    State(state): State<AppState>,
+    Query(params): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let fields = export_fields(params.fields.as_deref())?;
+
+    let filter = live(doc! {});
+    let cursor = timed(
+        &state,
+        "export_csv",
+        &filter,
+        state
+            .collection
+            .find(filter.clone())
+            .sort(doc! { "_id": 1 })
+            .max_time(state.config.long_timeout),
+    )
+    .await
+    .map_err(internal_error)?;
+
+    let header_row = csv_row(fields.iter().map(|f| f.to_string()));
+    let rows = cursor.map(move |article| match article {
+        Ok(article) => Ok(csv_row(fields.iter().map(|f| cell(&article, f)))),
+        Err(e) => {
+            tracing::error!("CSV export aborted: {}", e);
+            Err(e)
+        }
+    });
+    let body = Body::from_stream(stream::once(async { Ok(header_row) }).chain(rows));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"articles.csv\"",
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn export_fields(fields: Option<&str>) -> Result<Vec<&'static str>, ApiError> {
+    let Some(fields) = fields else {
+        return Ok(DEFAULT_EXPORT_FIELDS.to_vec());
+    };
+    let mut selected = Vec::new();
+    for name in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let Some(field) = EXPORT_FIELDS.iter().find(|f| **f == name) else {
+            return Err(bad_request(Message::UnknownExportField {
+                field: name.to_string(),
+                allowed: EXPORT_FIELDS.join(", "),
+            }));
+        };
+        if !selected.contains(field) {
+            selected.push(*field);
+        }
+    }
+    if selected.is_empty() {
+        return Ok(DEFAULT_EXPORT_FIELDS.to_vec());
+    }
+    Ok(selected)
+}
+
+fn cell(article: &Article, field: &str) -> String {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let list =
+        |value: &Option<Vec<String>>| value.as_ref().map(|v| v.join("|")).unwrap_or_default();
+    match field {
+        "slug" => article.slug.clone(),
+        "titre" => article.titre.clone(),
+        "categorie" => text(&article.categorie),
+        "theme" => text(&article.theme),
+        "author" => text(&article.author),
+        "tags" => list(&article.tags),
+        "status" => match article.status {
+            Some(crate::ArticleStatus::Draft) => "draft".to_string(),
+            Some(crate::ArticleStatus::Published) => "published".to_string(),
+            None => String::new(),
+        },
+        "date_publication" => text(&article.date_publication),
+        "updated_at" => text(&article.updated_at),
+        "petit_description" => text(&article.petit_description),
+        "photo" => text(&article.photo),
+        "photo_description" => text(&article.photo_description),
+        "seo_title" => text(&article.seo_title),
+        "seo_description" => text(&article.seo_description),
+        "seo_keywords" => list(&article.seo_keywords),
+        "series" => text(&article.series),
+        "series_order" => article
+            .series_order
+            .map(|o| o.to_string())
+            .unwrap_or_default(),
+        "related" => list(&article.related),
+        "contenu" => text(&article.contenu),
+        _ => String::new(),
+    }
+}
+
+/// One CSV line. Values containing a separator, quote or line break are
+/// quoted, with quotes doubled; embedded newlines stay inside the quotes.
+fn csv_row(values: impl Iterator<Item = String>) -> String {
+    let mut row = values
+        .map(|value| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push_str("\r\n");
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    fn row(values: &[&str]) -> String {
+        csv_row(values.iter().map(|v| v.to_string()))
+    }
+
+    #[test]
+    fn plain_values_are_not_quoted() {
+        assert_eq!(
+            row(&["mon-article", "Mon article", ""]),
+            "mon-article,Mon article,\r\n"
+        );
+    }
+
+    #[test]
+    fn separators_and_quotes_are_quoted() {
+        assert_eq!(row(&["a,b"]), "\"a,b\"\r\n");
+        assert_eq!(row(&["dit \"bonjour\""]), "\"dit \"\"bonjour\"\"\"\r\n");
+    }
+
+    #[test]
+    fn line_breaks_stay_inside_the_quotes() {
+        assert_eq!(
+            row(&["ligne 1\r\nligne 2", "x"]),
+            "\"ligne 1\r\nligne 2\",x\r\n"
+        );
+        assert_eq!(row(&["a\nb"]), "\"a\nb\"\r\n");
+    }
+
+    #[test]
+    fn unknown_fields_are_refused() {
+        let (status, body) = export_fields(Some("slug,mot_de_passe")).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.0.error.contains("mot_de_passe"));
+    }
+
+    #[test]
+    fn fields_keep_the_requested_order_once_each() {
+        assert_eq!(
+            export_fields(Some("titre, slug,titre,,tags")).unwrap(),
+            ["titre", "slug", "tags"]
+        );
+    }
+
+    #[test]
+    fn omitted_or_empty_fields_mean_the_defaults() {
+        assert_eq!(export_fields(None).unwrap(), DEFAULT_EXPORT_FIELDS);
+        assert_eq!(export_fields(Some(" , ")).unwrap(), DEFAULT_EXPORT_FIELDS);
+    }
+
+    #[test]
+    fn list_fields_are_joined_with_pipes() {
+        let article: Article = serde_json::from_value(serde_json::json!({
+            "slug": "a",
+            "titre": "A",
+            "tags": ["rust", "web"],
+            "series_order": 2,
+        }))
+        .unwrap();
+        assert_eq!(cell(&article, "tags"), "rust|web");
+        assert_eq!(cell(&article, "series_order"), "2");
+        assert_eq!(cell(&article, "author"), "");
+    }
+}
@@ -0,0 +1,632 @@
+//! Sitemap and syndication feeds. All of them link to the public article
+//! pages, so they are only served when `ARTICLE_BASE_URL` is configured.
+//!
+//! Catalogs past one sitemap's worth of URLs use `/sitemap-index.xml`,
+//! which points at `/sitemap-1.xml`, `/sitemap-2.xml`, … of
+//! `SITEMAP_CHUNK_SIZE` URLs each, in the same order as `/sitemap.xml`.
+
+use crate::{
+    api_error,
+    i18n::Message,
+    internal_error, published,
+    seo::{self, PUBLISHER_NAME},
+    text, timed, ApiError, AppState, Article,
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+
+/// Articles listed in the RSS and Atom feeds.
+const FEED_SIZE: i64 = 50;
+
+/// The sitemap protocol's limit on URLs per file.
+pub const SITEMAP_MAX_URLS: i64 = 50_000;
+
+const XML: &str = "application/xml; charset=utf-8";
+
+/// A feed listing articles. The sitemap index lists chunks instead and has
+/// its own path through `sitemap_index`.
+#[derive(Debug, Clone, Copy)]
+enum Feed {
+    Sitemap,
+    /// One `SITEMAP_CHUNK_SIZE` chunk of the sitemap, numbered from 1; see
+    /// `Feed::chunk`.
+    SitemapChunk {
+        skip: u64,
+        size: i64,
+    },
+    Rss,
+    Atom,
+}
+
+impl Feed {
+    /// Chunk `number` of `size` URLs; `None` for chunk 0 and for chunks so
+    /// far out that MongoDB couldn't skip to them, which can't exist.
+    fn chunk(number: u64, size: i64) -> Option<Feed> {
+        number
+            .checked_sub(1)?
+            .checked_mul(size as u64)
+            .filter(|skip| i64::try_from(*skip).is_ok())
+            .map(|skip| Feed::SitemapChunk { skip, size })
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Feed::Sitemap | Feed::SitemapChunk { .. } => XML,
+            Feed::Rss => "application/rss+xml; charset=utf-8",
+            Feed::Atom => "application/atom+xml; charset=utf-8",
+        }
+    }
+
+    /// `(skip, limit)` of the articles the feed lists.
+    fn window(self) -> (u64, i64) {
+        match self {
+            Feed::Sitemap => (0, SITEMAP_MAX_URLS),
+            Feed::SitemapChunk { skip, size } => (skip, size),
+            Feed::Rss | Feed::Atom => (0, FEED_SIZE),
+        }
+    }
+
+    /// Fields the feed reads. Only the RSS and Atom descriptions need the
+    /// body, for articles without a description.
+    fn projection(self) -> Document {
+        let mut projection = feed_projection();
+        if let Feed::Rss | Feed::Atom = self {
+            projection.insert("contenu", 1);
+        }
+        projection
+    }
+
+    fn render(
+        self,
+        articles: &[Article],
+        base_url: &str,
+        updated: Option<DateTime<Utc>>,
+        summary_chars: usize,
+    ) -> String {
+        match self {
+            Feed::Sitemap | Feed::SitemapChunk { .. } => render_sitemap(articles, base_url),
+            Feed::Rss => render_rss(articles, base_url, updated, summary_chars),
+            Feed::Atom => render_atom(articles, base_url, updated, summary_chars),
+        }
+    }
+}
+
+pub async fn sitemap(
+    State(state): State<AppState>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    serve(&state, &method, &headers, Feed::Sitemap).await
+}
+
+/// `/sitemap-{n}.xml`. The route captures `{n}.xml` whole, since a path
+/// parameter can't be followed by a suffix.
+pub async fn sitemap_chunk(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let chunk = file
+        .strip_suffix(".xml")
+        .and_then(|n| n.parse::<u64>().ok())
+        .and_then(|n| Feed::chunk(n, state.config.sitemap_chunk_size))
+        .ok_or_else(|| sitemap_not_found(format!("sitemap-{}", file)))?;
+    // Checked before `serve` so `HEAD` and `304` don't vouch for a chunk
+    // past the end of the catalog.
+    base_url(&state)?;
+    if !chunk_exists(&state, chunk).await? {
+        return Err(sitemap_not_found(format!("sitemap-{}", file)));
+    }
+    serve(&state, &method, &headers, chunk).await
+}
+
+/// Whether any published article falls in the chunk. Chunk 1 exists even
+/// for an empty catalog, like `/sitemap.xml`.
+async fn chunk_exists(state: &AppState, chunk: Feed) -> Result<bool, ApiError> {
+    let (skip, _) = chunk.window();
+    if skip == 0 {
+        return Ok(true);
+    }
+    let filter = published(doc! {});
+    let count = timed(
+        state,
+        "sitemap_chunk",
+        &filter,
+        state
+            .collection
+            .count_documents(filter.clone())
+            .skip(skip)
+            .limit(1)
+            .max_time(state.config.long_timeout),
+    )
+    .await
+    .map_err(internal_error)?;
+    Ok(count > 0)
+}
+
+/// Lists the chunks with the newest change in each, from `chunk_dates`.
+pub async fn sitemap_index(
+    State(state): State<AppState>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let conditional = Conditional::check(&state, XML).await?;
+    if let Some(response) = conditional.answer(&method, &headers) {
+        return Ok(response);
+    }
+    let chunks = chunk_dates(&state, state.config.sitemap_chunk_size).await?;
+    let sitemap_base = sitemap_base(conditional.base_url, &state.config.api_prefix);
+    let xml = render_sitemap_index(&chunks, &sitemap_base);
+    Ok((conditional.headers, xml).into_response())
+}
+
+pub async fn rss(
+    State(state): State<AppState>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    serve(&state, &method, &headers, Feed::Rss).await
+}
+
+pub async fn atom(
+    State(state): State<AppState>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    serve(&state, &method, &headers, Feed::Atom).await
+}
+
+/// The part every feed route shares: `HEAD` and conditional `GET`s are
+/// answered from the `Last-Modified` query alone, so crawlers checking for
+/// changes don't cost a full render.
+struct Conditional<'a> {
+    base_url: &'a str,
+    updated: Option<DateTime<Utc>>,
+    headers: HeaderMap,
+}
+
+impl<'a> Conditional<'a> {
+    async fn check(state: &'a AppState, content_type: &'static str) -> Result<Self, ApiError> {
+        let base_url = base_url(state)?;
+        let updated = last_modified(state).await?;
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        if let Some(value) = updated.and_then(|u| HeaderValue::from_str(&http_date(u)).ok()) {
+            headers.insert(header::LAST_MODIFIED, value);
+        }
+        Ok(Conditional {
+            base_url,
+            updated,
+            headers,
+        })
+    }
+
+    /// The response when there is nothing to render.
+    fn answer(&self, method: &Method, request: &HeaderMap) -> Option<Response> {
+        if is_not_modified(request, self.updated) {
+            return Some((StatusCode::NOT_MODIFIED, self.headers.clone()).into_response());
+        }
+        if method == Method::HEAD {
+            return Some(self.headers.clone().into_response());
+        }
+        None
+    }
+}
+
+async fn serve(
+    state: &AppState,
+    method: &Method,
+    headers: &HeaderMap,
+    feed: Feed,
+) -> Result<Response, ApiError> {
+    let conditional = Conditional::check(state, feed.content_type()).await?;
+    if let Some(response) = conditional.answer(method, headers) {
+        return Ok(response);
+    }
+
+    let filter = published(doc! {});
+    let (skip, limit) = feed.window();
+    let articles: Vec<Article> = timed(state, "feed", &filter, async {
+        state
+            .collection
+            .find(filter.clone())
+            .max_time(state.config.long_timeout)
+            .sort(feed_sort())
+            .skip(skip)
+            .limit(limit)
+            .projection(feed.projection())
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    let xml = feed.render(
+        &articles,
+        conditional.base_url,
+        conditional.updated,
+        state.config.snippet_max_chars,
+    );
+    Ok((conditional.headers, xml).into_response())
+}
+
+/// The article pages' base URL, without which there are no feeds.
+fn base_url(state: &AppState) -> Result<&str, ApiError> {
+    state
+        .config
+        .article_base_url
+        .as_deref()
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, Message::FeedsDisabled))
+}
+
+fn sitemap_not_found(file: String) -> ApiError {
+    api_error(StatusCode::NOT_FOUND, Message::SitemapNotFound { file })
+}
+
+fn feed_sort() -> Document {
+    doc! { "date_publication": -1, "_id": -1 }
+}
+
+/// Where the sitemap chunks are served. The protocol only lets a sitemap
+/// list URLs on its own host, so it has to be the article pages' origin.
+fn sitemap_base(base_url: &str, api_prefix: &str) -> String {
+    let origin_end = base_url
+        .find("://")
+        .and_then(|scheme| base_url[scheme + 3..].find('/').map(|i| scheme + 3 + i))
+        .unwrap_or(base_url.len());
+    format!("{}{}", &base_url[..origin_end], api_prefix)
+}
+
+/// Newest change in each chunk, walking the same order the chunks use.
+/// Only the two date fields are fetched, so even a large catalog streams
+/// through cheaply.
+async fn chunk_dates(
+    state: &AppState,
+    chunk_size: i64,
+) -> Result<Vec<Option<DateTime<Utc>>>, ApiError> {
+    let filter = published(doc! {});
+    timed(state, "sitemap_index", &filter, async {
+        let mut cursor = state
+            .collection
+            .find(filter.clone())
+            .max_time(state.config.long_timeout)
+            .sort(feed_sort())
+            .projection(doc! { "slug": 1, "titre": 1, "date_publication": 1, "updated_at": 1 })
+            .await?;
+        let mut chunks = Vec::new();
+        let mut in_chunk = chunk_size;
+        while let Some(article) = cursor.try_next().await? {
+            if in_chunk == chunk_size {
+                chunks.push(None);
+                in_chunk = 0;
+            }
+            in_chunk += 1;
+            if let Some(newest) = chunks.last_mut() {
+                *newest = (*newest).max(article_date(&article));
+            }
+        }
+        Ok(chunks)
+    })
+    .await
+    .map_err(internal_error)
+}
+
+fn feed_projection() -> Document {
+    doc! {
+        "slug": 1,
+        "titre": 1,
+        "petit_description": 1,
+        "seo_description": 1,
+        "date_publication": 1,
+        "updated_at": 1
+    }
+}
+
+/// Most recent change to the feeds: the later of the newest publication
+/// and the newest edit, each found with a one-document query. Edits are
+/// looked up across every document, not just live ones, since deleting,
+/// unpublishing or rescheduling an article stamps its `updated_at` and
+/// drops it from the feeds.
+async fn last_modified(state: &AppState) -> Result<Option<DateTime<Utc>>, ApiError> {
+    let mut latest = None;
+    for (field, filter) in [
+        (
+            "date_publication",
+            published(doc! { "date_publication": { "$ne": null } }),
+        ),
+        ("updated_at", doc! { "updated_at": { "$ne": null } }),
+    ] {
+        let article = timed(
+            state,
+            "last_modified",
+            &filter,
+            state
+                .collection
+                .find_one(filter.clone())
+                .max_time(state.config.read_timeout)
+                .sort(doc! { field: -1 })
+                .projection(feed_projection()),
+        )
+        .await
+        .map_err(internal_error)?;
+        // Only the field sorted on: a scheduled article's future
+        // `date_publication` must not count.
+        let stamp = article.and_then(|a| match field {
+            "date_publication" => a.date_publication,
+            _ => a.updated_at,
+        });
+        latest = latest.max(stamp.as_deref().and_then(parse_timestamp));
+    }
+    Ok(latest)
+}
+
+/// `true` when `If-Modified-Since` is at or after `updated`. HTTP-dates
+/// have second precision, so sub-second edits within that second count.
+fn is_not_modified(headers: &HeaderMap, updated: Option<DateTime<Utc>>) -> bool {
+    let Some(updated) = updated else {
+        return false;
+    };
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+        .is_some_and(|since| updated.timestamp() <= since.timestamp())
+}
+
+/// Last change of one article: `updated_at`, else `date_publication`.
+fn article_date(article: &Article) -> Option<DateTime<Utc>> {
+    let published = article
+        .date_publication
+        .as_deref()
+        .and_then(parse_timestamp);
+    let updated = article.updated_at.as_deref().and_then(parse_timestamp);
+    updated.max(published)
+}
+
+/// Stored dates are `YYYY-MM-DD` or RFC 3339; bare dates mean midnight UTC.
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc())
+}
+
+fn http_date(date: DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// The SEO description, else `petit_description`, else a snippet of the
+/// body, cut to `max_chars` like every generated description.
+fn summary(article: &Article, max_chars: usize) -> Option<String> {
+    let description = [&article.seo_description, &article.petit_description]
+        .into_iter()
+        .flatten()
+        .map(|s| s.trim())
+        .find(|s| !s.is_empty());
+    text::description_or_snippet(description, article.contenu.as_deref(), max_chars)
+}
+
+/// `article_url` for a base URL known to be configured.
+fn page_url(base_url: &str, slug: &str) -> String {
+    seo::article_url(Some(base_url), slug).unwrap_or_default()
+}
+
+fn render_sitemap(articles: &[Article], base_url: &str) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for article in articles {
+        xml.push_str("  <url>\n");
+        xml.push_str(&format!(
+            "    <loc>{}</loc>\n",
+            xml_escape(&page_url(base_url, &article.slug))
+        ));
+        if let Some(date) = article_date(article) {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", date.to_rfc3339()));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn render_sitemap_index(chunks: &[Option<DateTime<Utc>>], sitemap_base: &str) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (i, newest) in chunks.iter().enumerate() {
+        xml.push_str("  <sitemap>\n");
+        xml.push_str(&format!(
+            "    <loc>{}</loc>\n",
+            xml_escape(&format!("{}/sitemap-{}.xml", sitemap_base, i + 1))
+        ));
+        if let Some(date) = newest {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", date.to_rfc3339()));
+        }
+        xml.push_str("  </sitemap>\n");
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+fn render_rss(
+    articles: &[Article],
+    base_url: &str,
+    updated: Option<DateTime<Utc>>,
+    summary_chars: usize,
+) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n",
+    );
+    xml.push_str(&format!("  <title>{}</title>\n", PUBLISHER_NAME));
+    xml.push_str(&format!("  <link>{}</link>\n", xml_escape(base_url)));
+    xml.push_str(&format!(
+        "  <description>{}</description>\n",
+        PUBLISHER_NAME
+    ));
+    if let Some(updated) = updated {
+        xml.push_str(&format!(
+            "  <lastBuildDate>{}</lastBuildDate>\n",
+            updated.to_rfc2822()
+        ));
+    }
+    for article in articles {
+        let url = xml_escape(&page_url(base_url, &article.slug));
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            xml_escape(&article.titre)
+        ));
+        xml.push_str(&format!("    <link>{}</link>\n", url));
+        xml.push_str(&format!("    <guid isPermaLink=\"true\">{}</guid>\n", url));
+        if let Some(date) = article
+            .date_publication
+            .as_deref()
+            .and_then(parse_timestamp)
+        {
+            xml.push_str(&format!("    <pubDate>{}</pubDate>\n", date.to_rfc2822()));
+        }
+        if let Some(summary) = summary(article, summary_chars) {
+            xml.push_str(&format!(
+                "    <description>{}</description>\n",
+                xml_escape(&summary)
+            ));
+        }
+        xml.push_str("  </item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+fn render_atom(
+    articles: &[Article],
+    base_url: &str,
+    updated: Option<DateTime<Utc>>,
+    summary_chars: usize,
+) -> String {
+    let base = xml_escape(base_url);
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n",
+    );
+    xml.push_str(&format!("  <id>{}</id>\n", base));
+    xml.push_str(&format!("  <title>{}</title>\n", PUBLISHER_NAME));
+    xml.push_str(&format!("  <link href=\"{}\"/>\n", base));
+    // `updated` is mandatory in Atom; an empty feed falls back to now.
+    xml.push_str(&format!(
+        "  <updated>{}</updated>\n",
+        updated.unwrap_or_else(Utc::now).to_rfc3339()
+    ));
+    xml.push_str(&format!(
+        "  <author><name>{}</name></author>\n",
+        PUBLISHER_NAME
+    ));
+    for article in articles {
+        let url = xml_escape(&page_url(base_url, &article.slug));
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <id>{}</id>\n", url));
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            xml_escape(&article.titre)
+        ));
+        xml.push_str(&format!("    <link href=\"{}\"/>\n", url));
+        let entry_updated = article_date(article).or(updated).unwrap_or_else(Utc::now);
+        xml.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            entry_updated.to_rfc3339()
+        ));
+        if let Some(date) = article
+            .date_publication
+            .as_deref()
+            .and_then(parse_timestamp)
+        {
+            xml.push_str(&format!(
+                "    <published>{}</published>\n",
+                date.to_rfc3339()
+            ));
+        }
+        if let Some(summary) = summary(article, summary_chars) {
+            xml.push_str(&format!(
+                "    <summary>{}</summary>\n",
+                xml_escape(&summary)
+            ));
+        }
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(fields: serde_json::Value) -> Article {
+        let mut article = serde_json::json!({ "slug": "a", "titre": "A" });
+        article
+            .as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(article).unwrap()
+    }
+
+    #[test]
+    fn sitemap_chunk_windows() {
+        let window = |n, size| Feed::chunk(n, size).map(Feed::window);
+        assert_eq!(window(1, 1000), Some((0, 1000)));
+        assert_eq!(window(3, 1000), Some((2000, 1000)));
+        assert_eq!(window(0, 1000), None);
+        // Past what MongoDB can skip: a 404, not a failed query.
+        assert_eq!(window(u64::MAX, 1000), None);
+        assert_eq!(window(1 << 62, 4), None);
+    }
+
+    #[test]
+    fn summary_prefers_descriptions_over_the_body() {
+        let both = article(serde_json::json!({
+            "seo_description": "SEO",
+            "petit_description": "Petit",
+            "contenu": "Corps",
+        }));
+        assert_eq!(summary(&both, 160).as_deref(), Some("SEO"));
+        let petit = article(serde_json::json!({
+            "seo_description": "",
+            "petit_description": "Petit",
+        }));
+        assert_eq!(summary(&petit, 160).as_deref(), Some("Petit"));
+    }
+
+    #[test]
+    fn summary_falls_back_to_a_body_snippet() {
+        let body = article(serde_json::json!({ "contenu": "<p>Le corps de l'article</p>" }));
+        assert_eq!(summary(&body, 12).as_deref(), Some("Le corps de…"));
+        assert_eq!(summary(&article(serde_json::json!({})), 12), None);
+    }
+}
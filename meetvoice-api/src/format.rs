@@ -0,0 +1,261 @@
+//! Wire shape of ids and dates in JSON responses, for consumers that
+//! expect something other than the defaults: `ID_FORMAT` and
+//! `DATE_FORMAT`.
+
+use crate::{declared_path, AppState};
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, NaiveDate};
+use serde_json::Value;
+use std::{fmt, str::FromStr};
+
+/// Response fields holding a stored date string. Cursors such as
+/// `next_since` are left alone, since clients send them back as is.
+const DATE_FIELDS: &[&str] = &["date_publication", "updated_at", "deleted_at", "expires_at"];
+
+/// Routes whose JSON is sent as stored whatever the formats say. Paths as
+/// declared in `main`, without `API_PREFIX`.
+const UNFORMATTED_ROUTES: &[&str] = &["/admin/articles/:slug/raw"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IdFormat {
+    /// `{"$oid": "…"}`, as the BSON types serialize.
+    #[default]
+    Extended,
+    /// The bare hex string.
+    Hex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DateFormat {
+    /// The stored `YYYY-MM-DD` or RFC 3339 string.
+    #[default]
+    Iso,
+    /// Milliseconds since the Unix epoch; `YYYY-MM-DD` is midnight UTC.
+    EpochMillis,
+}
+
+#[derive(Debug)]
+pub struct UnknownFormat(&'static str);
+
+impl fmt::Display for UnknownFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected one of {}", self.0)
+    }
+}
+
+impl FromStr for IdFormat {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "extended" => Ok(IdFormat::Extended),
+            "hex" => Ok(IdFormat::Hex),
+            _ => Err(UnknownFormat("extended, hex")),
+        }
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "iso" => Ok(DateFormat::Iso),
+            "epoch_millis" => Ok(DateFormat::EpochMillis),
+            _ => Err(UnknownFormat("iso, epoch_millis")),
+        }
+    }
+}
+
+/// Rewrites JSON response bodies into the configured formats. With both
+/// defaults, and for `UNFORMATTED_ROUTES`, responses pass through
+/// untouched and nothing is buffered.
+pub async fn reformat_json(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let ids = state.config.id_format;
+    let dates = state.config.date_format;
+    let unformatted = declared_path(&req, &state.config.api_prefix)
+        .is_some_and(|path| UNFORMATTED_ROUTES.contains(&path));
+    let response = next.run(req).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if (ids, dates) == (IdFormat::default(), DateFormat::default()) || unformatted || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for reformatting: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    reformat(&mut value, ids, dates);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
+
+fn reformat(value: &mut Value, ids: IdFormat, dates: DateFormat) {
+    match value {
+        Value::Object(map) => {
+            if ids == IdFormat::Hex && map.len() == 1 {
+                if let Some(Value::String(hex)) = map.get("$oid") {
+                    *value = Value::String(hex.clone());
+                    return;
+                }
+            }
+            for (key, field) in map.iter_mut() {
+                if dates == DateFormat::EpochMillis && DATE_FIELDS.contains(&key.as_str()) {
+                    if let Some(millis) = field.as_str().and_then(epoch_millis) {
+                        *field = Value::from(millis);
+                        continue;
+                    }
+                }
+                reformat(field, ids, dates);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                reformat(item, ids, dates);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A stored date string as epoch milliseconds; `None` when it doesn't
+/// parse, in which case it is sent unchanged.
+fn epoch_millis(value: &str) -> Option<i64> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.timestamp_millis());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc().timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, tests::test_state};
+    use axum::{middleware, routing::get, Router};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn stored() -> Value {
+        json!({
+            "_id": { "$oid": "65a1b2c3d4e5f60718293a4b" },
+            "date_publication": "2024-01-02",
+            "updated_at": "2024-01-02T10:00:00.000Z",
+            "next_since": "2024-01-02",
+            "related": [{ "_id": { "$oid": "65a1b2c3d4e5f60718293a4c" } }],
+        })
+    }
+
+    fn reformatted(ids: IdFormat, dates: DateFormat) -> Value {
+        let mut value = stored();
+        reformat(&mut value, ids, dates);
+        value
+    }
+
+    #[test]
+    fn defaults_leave_the_stored_shape() {
+        assert_eq!(reformatted(IdFormat::Extended, DateFormat::Iso), stored());
+    }
+
+    #[test]
+    fn hex_ids_unwrap_every_oid() {
+        let value = reformatted(IdFormat::Hex, DateFormat::Iso);
+        assert_eq!(value["_id"], "65a1b2c3d4e5f60718293a4b");
+        assert_eq!(value["related"][0]["_id"], "65a1b2c3d4e5f60718293a4c");
+        assert_eq!(value["date_publication"], "2024-01-02");
+    }
+
+    #[test]
+    fn epoch_millis_converts_date_fields_only() {
+        let value = reformatted(IdFormat::Extended, DateFormat::EpochMillis);
+        assert_eq!(value["_id"], stored()["_id"]);
+        assert_eq!(value["date_publication"], 1_704_153_600_000_i64);
+        assert_eq!(value["updated_at"], 1_704_189_600_000_i64);
+        // Cursors go back to us as sent.
+        assert_eq!(value["next_since"], "2024-01-02");
+    }
+
+    #[test]
+    fn both_formats_combine() {
+        let value = reformatted(IdFormat::Hex, DateFormat::EpochMillis);
+        assert_eq!(value["_id"], "65a1b2c3d4e5f60718293a4b");
+        assert_eq!(value["date_publication"], 1_704_153_600_000_i64);
+    }
+
+    #[test]
+    fn unparseable_dates_are_sent_unchanged() {
+        let mut value = json!({ "date_publication": "bientôt" });
+        reformat(&mut value, IdFormat::Hex, DateFormat::EpochMillis);
+        assert_eq!(value["date_publication"], "bientôt");
+    }
+
+    /// The body `path` answers through `reformat_json` under `vars`.
+    async fn body(vars: &[(&str, &str)], path: &str) -> String {
+        let config = Config::from_lookup(|key| {
+            vars.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        })
+        .unwrap();
+        let state = test_state(config).await;
+        // Spaced like no serializer would, to see whether it was rewritten.
+        let handler = || async {
+            (
+                [(header::CONTENT_TYPE, "application/json")],
+                r#"{ "_id": { "$oid": "65a1b2c3d4e5f60718293a4b" } }"#,
+            )
+        };
+        let app = Router::new()
+            .route("/articles/:slug", get(handler))
+            .route("/admin/articles/:slug/raw", get(handler))
+            .layer(middleware::from_fn_with_state(state.clone(), reformat_json))
+            .with_state(state);
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn defaults_pass_responses_through() {
+        assert_eq!(
+            body(&[], "/articles/a").await,
+            r#"{ "_id": { "$oid": "65a1b2c3d4e5f60718293a4b" } }"#
+        );
+    }
+
+    #[tokio::test]
+    async fn configured_formats_rewrite_responses() {
+        assert_eq!(
+            body(&[("ID_FORMAT", "hex")], "/articles/a").await,
+            r#"{"_id":"65a1b2c3d4e5f60718293a4b"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn raw_documents_keep_the_stored_shape() {
+        assert_eq!(
+            body(&[("ID_FORMAT", "hex")], "/admin/articles/a/raw").await,
+            r#"{ "_id": { "$oid": "65a1b2c3d4e5f60718293a4b" } }"#
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use axum::{extract::Request, http::header, middleware::Next, response::Response};
+
+/// Languages error messages are available in. French is the default since
+/// the content is French.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Lang {
+    #[default]
+    Fr,
+    En,
+}
+
+tokio::task_local! {
+    static LANG: Lang;
+}
+
+impl Lang {
+    /// Picks the highest-weighted supported language from an
+    /// `Accept-Language` value, falling back to French.
+    pub fn from_accept_language(value: &str) -> Lang {
+        let mut best: Option<(f32, Lang)> = None;
+        for part in value.split(',') {
+            let mut pieces = part.split(';');
+            let tag = pieces.next().unwrap_or("").trim().to_ascii_lowercase();
+            let weight = pieces
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let lang = match tag.split('-').next() {
+                Some("fr") => Lang::Fr,
+                Some("en") => Lang::En,
+                _ => continue,
+            };
+            if weight > 0.0 && !matches!(best, Some((w, _)) if w >= weight) {
+                best = Some((weight, lang));
+            }
+        }
+        best.map(|(_, lang)| lang).unwrap_or_default()
+    }
+
+    /// Language of the request being handled.
+    pub fn current() -> Lang {
+        LANG.try_with(|lang| *lang).unwrap_or_default()
+    }
+}
+
+/// Makes the request's `Accept-Language` available to `Lang::current` while
+/// the rest of the stack runs.
+pub async fn negotiate_language(req: Request, next: Next) -> Response {
+    let lang = req
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .map(Lang::from_accept_language)
+        .unwrap_or_default();
+    LANG.scope(lang, next.run(req)).await
+}
+
+/// Catalog of client-facing error messages. Add a variant and its
+/// translations here for every new error.
+#[derive(Debug, Clone)]
+pub enum Message {
+    ArticleNotFound { slug: String },
+    ArticleExists { slug: String },
+    SeriesNotFound { slug: String },
+    UnknownCategory { value: String },
+    UnknownTheme { value: String },
+    ModifiedSince { slug: String },
+    InvalidDate { param: &'static str, value: String },
+    NotPositive { param: &'static str },
+    TooLong { param: &'static str, max: usize },
+    BatchTooLarge { max: usize },
+    OffsetTooLarge { max: u64 },
+    InvalidSort { key: String, allowed: String },
+    ConflictingParams(&'static str, &'static str),
+    UnknownFacet { field: String, allowed: String },
+    UnknownExportField { field: String, allowed: String },
+    ConfirmRequired,
+    EmptyFilter,
+    EmptyQuery,
+    FeedsDisabled,
+    SitemapNotFound { file: String },
+    Unauthorized,
+    Maintenance,
+    InvalidPreviewToken,
+    PreviewDisabled,
+    Timeout,
+    ValidationFailed,
+    Database(String),
+}
+
+impl Message {
+    pub fn text(&self, lang: Lang) -> String {
+        match (self, lang) {
+            (Message::ArticleNotFound { slug }, Lang::Fr) => {
+                format!("Article '{}' introuvable", slug)
+            }
+            (Message::ArticleNotFound { slug }, Lang::En) => {
+                format!("Article '{}' not found", slug)
+            }
+            (Message::ArticleExists { slug }, Lang::Fr) => {
+                format!("L'article '{}' existe déjà", slug)
+            }
+            (Message::ArticleExists { slug }, Lang::En) => {
+                format!("Article '{}' already exists", slug)
+            }
+            (Message::SeriesNotFound { slug }, Lang::Fr) => {
+                format!("Série '{}' introuvable", slug)
+            }
+            (Message::SeriesNotFound { slug }, Lang::En) => {
+                format!("Series '{}' not found", slug)
+            }
+            (Message::UnknownCategory { value }, Lang::Fr) => {
+                format!("Catégorie '{}' inconnue", value)
+            }
+            (Message::UnknownCategory { value }, Lang::En) => {
+                format!("Unknown category '{}'", value)
+            }
+            (Message::UnknownTheme { value }, Lang::Fr) => format!("Thème '{}' inconnu", value),
+            (Message::UnknownTheme { value }, Lang::En) => format!("Unknown theme '{}'", value),
+            (Message::ModifiedSince { slug }, Lang::Fr) => {
+                format!("L'article '{}' a été modifié entre-temps", slug)
+            }
+            (Message::ModifiedSince { slug }, Lang::En) => {
+                format!("Article '{}' was modified since", slug)
+            }
+            (Message::InvalidDate { param, value }, Lang::Fr) => format!(
+                "Date '{}' invalide pour '{}' : format attendu AAAA-MM-JJ ou RFC 3339",
+                value, param
+            ),
+            (Message::InvalidDate { param, value }, Lang::En) => format!(
+                "Invalid '{}' date '{}': expected YYYY-MM-DD or RFC 3339",
+                param, value
+            ),
+            (Message::NotPositive { param }, Lang::Fr) => {
+                format!("'{}' doit être un entier positif", param)
+            }
+            (Message::NotPositive { param }, Lang::En) => {
+                format!("'{}' must be a positive integer", param)
+            }
+            (Message::TooLong { param, max }, Lang::Fr) => {
+                format!("'{}' ne doit pas dépasser {} caractères", param, max)
+            }
+            (Message::TooLong { param, max }, Lang::En) => {
+                format!("'{}' must be at most {} characters", param, max)
+            }
+            (Message::BatchTooLarge { max }, Lang::Fr) => {
+                format!("Au plus {} éléments par requête", max)
+            }
+            (Message::BatchTooLarge { max }, Lang::En) => {
+                format!("At most {} items per request", max)
+            }
+            (Message::OffsetTooLarge { max }, Lang::Fr) => format!(
+                "Pagination trop profonde : au plus {} articles peuvent être sautés. \
+                 Restreignez la période avec date_from/date_to plutôt que d'augmenter page",
+                max
+            ),
+            (Message::OffsetTooLarge { max }, Lang::En) => format!(
+                "Page too deep: at most {} articles can be skipped. \
+                 Narrow the range with date_from/date_to instead of raising page",
+                max
+            ),
+            (Message::InvalidSort { key, allowed }, Lang::Fr) => format!(
+                "Tri '{}' invalide : format attendu champ:asc|desc, sans doublon, \
+                 champs acceptés : {}",
+                key, allowed
+            ),
+            (Message::InvalidSort { key, allowed }, Lang::En) => format!(
+                "Invalid sort key '{}': expected field:asc|desc without repeats, \
+                 allowed fields: {}",
+                key, allowed
+            ),
+            (Message::ConflictingParams(first, second), Lang::Fr) => format!(
+                "Les paramètres '{}' et '{}' sont contradictoires ; n'en gardez qu'un \
+                 ou corrigez leurs valeurs",
+                first, second
+            ),
+            (Message::ConflictingParams(first, second), Lang::En) => format!(
+                "Parameters '{}' and '{}' contradict each other; drop one or fix their values",
+                first, second
+            ),
+            (Message::UnknownFacet { field, allowed }, Lang::Fr) => format!(
+                "Facette '{}' inconnue, champs acceptés : {}",
+                field, allowed
+            ),
+            (Message::UnknownFacet { field, allowed }, Lang::En) => {
+                format!("Unknown facet '{}', allowed fields: {}", field, allowed)
+            }
+            (Message::UnknownExportField { field, allowed }, Lang::Fr) => format!(
+                "Colonne '{}' inconnue, colonnes acceptées : {}",
+                field, allowed
+            ),
+            (Message::UnknownExportField { field, allowed }, Lang::En) => {
+                format!("Unknown column '{}', allowed columns: {}", field, allowed)
+            }
+            (Message::ConfirmRequired, Lang::Fr) => {
+                "Confirmation requise : envoyez \"confirm\": true".into()
+            }
+            (Message::ConfirmRequired, Lang::En) => {
+                "Confirmation required: send \"confirm\": true".into()
+            }
+            (Message::EmptyFilter, Lang::Fr) => "Au moins un critère de filtre est requis".into(),
+            (Message::EmptyFilter, Lang::En) => "At least one filter is required".into(),
+            (Message::EmptyQuery, Lang::Fr) => "Le paramètre 'q' ne doit pas être vide".into(),
+            (Message::EmptyQuery, Lang::En) => "Parameter 'q' must not be empty".into(),
+            (Message::FeedsDisabled, Lang::Fr) => {
+                "Flux indisponibles : ARTICLE_BASE_URL n'est pas configurée".into()
+            }
+            (Message::FeedsDisabled, Lang::En) => {
+                "Feeds unavailable: ARTICLE_BASE_URL is not configured".into()
+            }
+            (Message::SitemapNotFound { file }, Lang::Fr) => {
+                format!("Sitemap '{}' introuvable", file)
+            }
+            (Message::SitemapNotFound { file }, Lang::En) => {
+                format!("Sitemap '{}' not found", file)
+            }
+            (Message::Unauthorized, Lang::Fr) => {
+                "Jeton d'administration manquant ou invalide".into()
+            }
+            (Message::Unauthorized, Lang::En) => "Missing or invalid admin token".into(),
+            (Message::InvalidPreviewToken, Lang::Fr) => "Lien d'aperçu invalide ou expiré".into(),
+            (Message::InvalidPreviewToken, Lang::En) => "Invalid or expired preview link".into(),
+            (Message::PreviewDisabled, Lang::Fr) => {
+                "Aperçus indisponibles : PREVIEW_SECRET n'est pas configuré".into()
+            }
+            (Message::PreviewDisabled, Lang::En) => {
+                "Previews unavailable: PREVIEW_SECRET is not configured".into()
+            }
+            (Message::Maintenance, Lang::Fr) => {
+                "Maintenance en cours : les modifications sont suspendues, réessayez plus tard"
+                    .into()
+            }
+            (Message::Maintenance, Lang::En) => {
+                "Under maintenance: changes are paused, try again later".into()
+            }
+            (Message::Timeout, Lang::Fr) => "Délai de réponse dépassé".into(),
+            (Message::Timeout, Lang::En) => "Request timed out".into(),
+            (Message::ValidationFailed, Lang::Fr) => "Échec de la validation".into(),
+            (Message::ValidationFailed, Lang::En) => "Validation failed".into(),
+            (Message::Database(e), Lang::Fr) => format!("Erreur de base de données : {}", e),
+            (Message::Database(e), Lang::En) => format!("Database error: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lang(header: &str) -> Lang {
+        Lang::from_accept_language(header)
+    }
+
+    #[test]
+    fn single_languages() {
+        assert_eq!(lang("en"), Lang::En);
+        assert_eq!(lang("fr"), Lang::Fr);
+        assert_eq!(lang(" EN "), Lang::En);
+    }
+
+    #[test]
+    fn highest_q_value_wins() {
+        assert_eq!(lang("fr;q=0.5, en;q=0.9"), Lang::En);
+        assert_eq!(lang("en;q=0.4,fr"), Lang::Fr);
+        // Equal weights keep the first listed.
+        assert_eq!(lang("en;q=0.8, fr;q=0.8"), Lang::En);
+        // `q=0` means "not this one".
+        assert_eq!(lang("en;q=0"), Lang::Fr);
+        // An unreadable weight counts as the default `1`.
+        assert_eq!(lang("fr;q=0.9, en;q=abc"), Lang::En);
+    }
+
+    #[test]
+    fn region_subtags_match_their_language() {
+        assert_eq!(lang("fr-CA"), Lang::Fr);
+        assert_eq!(lang("en-GB,fr-CA;q=0.7"), Lang::En);
+        assert_eq!(lang("de-DE, en-US;q=0.3"), Lang::En);
+    }
+
+    #[test]
+    fn unsupported_wildcard_and_empty_headers_fall_back_to_french() {
+        assert_eq!(lang(""), Lang::Fr);
+        assert_eq!(lang("*"), Lang::Fr);
+        assert_eq!(lang("de, es;q=0.8"), Lang::Fr);
+        assert_eq!(Lang::default(), Lang::Fr);
+    }
+
+    #[test]
+    fn current_is_the_default_outside_a_request() {
+        assert_eq!(Lang::current(), Lang::Fr);
+    }
+}
@@ -0,0 +1,180 @@
+//! Signed, expiring links that show an unpublished article to reviewers
+//! without an admin token.
+//!
+//! A token is `{expiry}.{signature}`: the Unix expiry time and the hex
+//! HMAC-SHA256 of `{slug}.{expiry}` under `PREVIEW_SECRET`. Nothing is
+//! stored, so rotating the secret revokes every outstanding link.
+
+use crate::{
+    api_error, config::Config, i18n::Message, normalize_slug, token_matches, writes, ApiError,
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+#[derive(Debug, Serialize)]
+pub struct PreviewToken {
+    token: String,
+    expires_at: String,
+    /// Path of the article with the token applied, relative to the host.
+    path: String,
+}
+
+/// Issues a preview link for any live article, draft or not.
+pub async fn issue_token(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<PreviewToken>, ApiError> {
+    let secret = secret(&state.config)?;
+    let slug = normalize_slug(&slug);
+    writes::find_article(&state, &slug).await?;
+
+    let expiry = Utc::now() + state.config.preview_token_ttl;
+    let token = token(secret, &slug, expiry);
+    Ok(Json(PreviewToken {
+        path: format!(
+            "{}/articles/{}?preview_token={}",
+            state.config.api_prefix, slug, token
+        ),
+        expires_at: expiry.to_rfc3339(),
+        token,
+    }))
+}
+
+/// Checks a `preview_token` for `slug`, answering `403` when it is
+/// malformed, expired or signed for another article.
+pub fn verify(config: &Config, slug: &str, token: &str) -> Result<(), ApiError> {
+    let secret = secret(config)?;
+    let forbidden = || api_error(StatusCode::FORBIDDEN, Message::InvalidPreviewToken);
+    let (expiry, signature) = token.split_once('.').ok_or_else(forbidden)?;
+    let expiry: i64 = expiry.parse().map_err(|_| forbidden())?;
+    let expired = DateTime::from_timestamp(expiry, 0).is_none_or(|e| e <= Utc::now());
+    let expected = sign(secret, slug, expiry);
+    if expired || !token_matches(signature.as_bytes(), expected.as_bytes()) {
+        return Err(forbidden());
+    }
+    Ok(())
+}
+
+fn secret(config: &Config) -> Result<&str, ApiError> {
+    config
+        .preview_secret
+        .as_deref()
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, Message::PreviewDisabled))
+}
+
+fn token(secret: &str, slug: &str, expiry: DateTime<Utc>) -> String {
+    let expiry = expiry.timestamp();
+    format!("{}.{}", expiry, sign(secret, slug, expiry))
+}
+
+fn sign(secret: &str, slug: &str, expiry: i64) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", slug, expiry).as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "un-secret-de-test-assez-long-pour-hmac";
+
+    fn config(secret: Option<&str>) -> Config {
+        Config::from_lookup(|key| match key {
+            "PREVIEW_SECRET" => secret.map(str::to_string),
+            _ => None,
+        })
+        .unwrap()
+    }
+
+    fn status(result: Result<(), ApiError>) -> StatusCode {
+        result.unwrap_err().0
+    }
+
+    fn in_an_hour() -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::hours(1)
+    }
+
+    #[test]
+    fn token_opens_its_own_article() {
+        let token = token(SECRET, "brouillon", in_an_hour());
+        assert!(verify(&config(Some(SECRET)), "brouillon", &token).is_ok());
+    }
+
+    #[test]
+    fn token_is_refused_for_another_article() {
+        let token = token(SECRET, "brouillon", in_an_hour());
+        let result = verify(&config(Some(SECRET)), "autre-article", &token);
+        assert_eq!(status(result), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn tampered_tokens_are_refused() {
+        let config = config(Some(SECRET));
+        let token = token(SECRET, "brouillon", in_an_hour());
+        let (expiry, signature) = token.split_once('.').unwrap();
+        let flipped = if signature.starts_with('0') { "1" } else { "0" };
+        let bad_signature = format!("{}.{}{}", expiry, flipped, &signature[1..]);
+        assert_eq!(
+            status(verify(&config, "brouillon", &bad_signature)),
+            StatusCode::FORBIDDEN
+        );
+        // Pushing the expiry back breaks the signature too.
+        let later = format!("{}.{}", expiry.parse::<i64>().unwrap() + 3600, signature);
+        assert_eq!(
+            status(verify(&config, "brouillon", &later)),
+            StatusCode::FORBIDDEN
+        );
+        let other_secret = super::token("autre-secret", "brouillon", in_an_hour());
+        assert_eq!(
+            status(verify(&config, "brouillon", &other_secret)),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[test]
+    fn expired_tokens_are_refused() {
+        let expired = token(
+            SECRET,
+            "brouillon",
+            Utc::now() - chrono::Duration::seconds(1),
+        );
+        let result = verify(&config(Some(SECRET)), "brouillon", &expired);
+        assert_eq!(status(result), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn malformed_tokens_are_refused() {
+        let config = config(Some(SECRET));
+        for token in ["", "abc", "demain.abcdef", ".", "99999999999999999999.ab"] {
+            assert_eq!(
+                status(verify(&config, "brouillon", token)),
+                StatusCode::FORBIDDEN,
+                "{}",
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn previews_are_not_found_without_a_secret() {
+        let token = token(SECRET, "brouillon", in_an_hour());
+        assert_eq!(
+            status(verify(&config(None), "brouillon", &token)),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
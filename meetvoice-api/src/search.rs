@@ -0,0 +1,196 @@
+//! Full-text search over articles, backed by a MongoDB text index.
+
+use crate::{
+    bad_request, deserialize_lenient, i18n::Message, internal_error, list_projection, published,
+    text, timed, ApiError, AppState, Article, ArticleListItem, ListResponse, Page, Paginated,
+};
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+
+pub const TEXT_INDEX_NAME: &str = "article_text";
+
+/// Relative weight of each field in the text score: a tag or author match
+/// outranks a title match, which outranks a match deep in the body.
+const TEXT_WEIGHTS: &[(&str, i32)] = &[
+    ("tags", 10),
+    ("titre", 8),
+    ("author", 6),
+    ("petit_description", 4),
+    ("seo_keywords", 4),
+    ("contenu", 1),
+];
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    q: String,
+    page: Option<u64>,
+    limit: Option<i64>,
+}
+
+fn text_weights() -> Document {
+    TEXT_WEIGHTS
+        .iter()
+        .map(|(field, weight)| (field.to_string(), Bson::Int32(*weight)))
+        .collect()
+}
+
+/// Creates the text index, replacing any text index with a different field
+/// list or weights. A collection can only have one text index and MongoDB
+/// refuses to change one in place, so a spec change means drop and rebuild.
+pub async fn ensure_indexes(collection: &Collection<Article>) -> mongodb::error::Result<()> {
+    let weights = text_weights();
+    let existing: Vec<IndexModel> = collection.list_indexes().await?.try_collect().await?;
+    for index in existing {
+        let is_text = index.keys.values().any(|v| v.as_str() == Some("text"));
+        let Some(options) = index.options.filter(|_| is_text) else {
+            continue;
+        };
+        let same = options.name.as_deref() == Some(TEXT_INDEX_NAME)
+            && options
+                .weights
+                .as_ref()
+                .is_some_and(|w| same_weights(w, &weights));
+        if same {
+            return Ok(());
+        }
+        if let Some(name) = options.name {
+            tracing::info!("Replacing text index '{}'", name);
+            collection.drop_index(name).await?;
+        }
+    }
+
+    let keys: Document = TEXT_WEIGHTS
+        .iter()
+        .map(|(field, _)| (field.to_string(), Bson::String("text".into())))
+        .collect();
+    let index = IndexModel::builder()
+        .keys(keys)
+        .options(
+            IndexOptions::builder()
+                .name(TEXT_INDEX_NAME.to_string())
+                .weights(weights)
+                .default_language("french".to_string())
+                .build(),
+        )
+        .build();
+    collection.create_index(index).await?;
+    Ok(())
+}
+
+/// MongoDB may report weights with a different numeric type or order.
+fn same_weights(stored: &Document, wanted: &Document) -> bool {
+    let number = |b: &Bson| match b {
+        Bson::Int32(n) => Some(*n as i64),
+        Bson::Int64(n) => Some(*n),
+        Bson::Double(n) => Some(*n as i64),
+        _ => None,
+    };
+    stored.len() == wanted.len()
+        && wanted
+            .iter()
+            .all(|(field, weight)| stored.get(field).and_then(number) == number(weight))
+}
+
+/// Articles matching `q` in their title, body, description, keywords,
+/// tags or author, best matches first. Uses MongoDB's French text search:
+/// words are stemmed, `"quoted phrases"` must match exactly and `-word`
+/// excludes. Results without a `petit_description` get a snippet of the
+/// body in its place.
+pub async fn search_articles(
+    State(state): State<AppState>,
+    Query(params): Query<SearchQuery>,
+) -> Result<Json<ListResponse>, ApiError> {
+    let q = params.q.trim();
+    if q.is_empty() {
+        return Err(bad_request(Message::EmptyQuery));
+    }
+    let paging = Page::new(params.page, params.limit, &state.config)?;
+    let Page { limit, skip, .. } = paging;
+    let filter = published(doc! { "$text": { "$search": q } });
+
+    let total = timed(
+        &state,
+        "search_articles",
+        &filter,
+        state
+            .collection
+            .count_documents(filter.clone())
+            .max_time(state.config.read_timeout),
+    )
+    .await
+    .map_err(internal_error)?;
+
+    let mut projection = list_projection();
+    projection.insert("score", doc! { "$meta": "textScore" });
+    projection.insert("contenu", 1);
+    let mut documents: Vec<Document> = timed(&state, "search_articles", &filter, async {
+        state
+            .collection
+            .clone_with_type::<Document>()
+            .find(filter.clone())
+            .max_time(state.config.read_timeout)
+            .sort(doc! { "score": { "$meta": "textScore" }, "_id": 1 })
+            .skip(skip)
+            .limit(limit)
+            .projection(projection)
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+    for document in &mut documents {
+        with_snippet(document, state.config.snippet_max_chars);
+    }
+    let (articles, warnings) = deserialize_lenient::<ArticleListItem>(documents, "search_articles");
+
+    Ok(Json(
+        Paginated::new(articles, total, paging).with_warnings(warnings),
+    ))
+}
+
+/// Replaces the body loaded for the snippet with the result's description:
+/// `petit_description`, else a plain-text snippet of `contenu`.
+fn with_snippet(document: &mut Document, max_chars: usize) {
+    let contenu = document.remove("contenu");
+    let snippet = text::description_or_snippet(
+        document.get_str("petit_description").ok(),
+        contenu.as_ref().and_then(Bson::as_str),
+        max_chars,
+    );
+    if let Some(snippet) = snippet {
+        document.insert("petit_description", snippet);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_snippet_falls_back_to_the_body() {
+        let mut document = doc! {
+            "slug": "a",
+            "petit_description": " ",
+            "contenu": "<p>Un <em>long</em> texte</p>",
+        };
+        with_snippet(&mut document, 10);
+        assert_eq!(
+            document,
+            doc! { "slug": "a", "petit_description": "Un long…" }
+        );
+
+        let mut document = doc! { "petit_description": "Résumé", "contenu": "Texte" };
+        with_snippet(&mut document, 10);
+        assert_eq!(document, doc! { "petit_description": "Résumé" });
+    }
+}
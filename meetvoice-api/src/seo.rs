@@ -0,0 +1,244 @@
+//! Structured metadata built from articles for search engines and partners.
+
+use crate::{
+    find_by_slug, live, normalize_slug, not_found, published, writes, ApiError, AppState, Article,
+    ArticleQuery,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+};
+use mongodb::options::FindOneOptions;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+pub const PUBLISHER_NAME: &str = "MeetVoice";
+
+/// Public URL of an article, when `ARTICLE_BASE_URL` is configured.
+pub fn article_url(base_url: Option<&str>, slug: &str) -> Option<String> {
+    base_url.map(|base| format!("{}/{}", base, slug))
+}
+
+/// schema.org `NewsArticle` for the article. Fields the article lacks are
+/// left out rather than emitted empty.
+pub fn json_ld(article: &Article, base_url: Option<&str>) -> Value {
+    let mut ld = Map::new();
+    ld.insert("@context".into(), json!("https://schema.org"));
+    ld.insert("@type".into(), json!("NewsArticle"));
+    ld.insert("headline".into(), json!(article.titre));
+
+    let description = article
+        .seo_description
+        .as_ref()
+        .or(article.petit_description.as_ref());
+    let fields = [
+        ("description", description),
+        ("image", article.photo.as_ref()),
+        ("datePublished", article.date_publication.as_ref()),
+        ("dateModified", article.updated_at.as_ref()),
+        ("articleSection", article.categorie.as_ref()),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+            ld.insert(key.into(), json!(value));
+        }
+    }
+    if let Some(keywords) = article.seo_keywords.as_ref().filter(|k| !k.is_empty()) {
+        ld.insert("keywords".into(), json!(keywords.join(", ")));
+    }
+    if let Some(url) = article_url(base_url, &article.slug) {
+        ld.insert("url".into(), json!(url));
+        ld.insert(
+            "mainEntityOfPage".into(),
+            json!({ "@type": "WebPage", "@id": url }),
+        );
+    }
+    ld.insert(
+        "publisher".into(),
+        json!({ "@type": "Organization", "name": PUBLISHER_NAME }),
+    );
+    Value::Object(ld)
+}
+
+/// Downloadable JSON-LD file for one article.
+pub async fn article_json_ld(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Response, ApiError> {
+    let slug = normalize_slug(&slug);
+    let article = find_by_slug(
+        &state,
+        "article_json_ld",
+        &state.collection,
+        &slug,
+        published,
+        FindOneOptions::default(),
+    )
+    .await?
+    .ok_or_else(|| not_found(&slug))?;
+
+    let body = json_ld(&article, state.config.article_base_url.as_deref());
+    let filename: String = article
+        .slug
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/ld+json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.jsonld\"", filename),
+            ),
+        ],
+        body.to_string(),
+    )
+        .into_response())
+}
+
+/// Open Graph and Twitter Card meta tags, keyed by their `property` or
+/// `name`. Tags with no value are left out.
+#[derive(Debug, Serialize)]
+pub struct SocialTags {
+    open_graph: Map<String, Value>,
+    twitter: Map<String, Value>,
+}
+
+/// Social tags for an article whose SEO defaults are already applied.
+/// Without a photo there is no image tag and the Twitter card falls back
+/// to `summary`.
+fn social_tags(article: &Article, base_url: Option<&str>) -> SocialTags {
+    let image = article.photo.as_deref().filter(|p| !p.trim().is_empty());
+    let url = article_url(base_url, &article.slug);
+    let title = article.seo_title.as_deref();
+    let description = article.seo_description.as_deref();
+
+    let mut open_graph = Map::new();
+    let mut twitter = Map::new();
+    let put = |tags: &mut Map<String, Value>, key: &str, value: Option<&str>| {
+        if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+            tags.insert(key.into(), json!(value));
+        }
+    };
+    put(&mut open_graph, "og:type", Some("article"));
+    put(&mut open_graph, "og:site_name", Some(PUBLISHER_NAME));
+    put(&mut open_graph, "og:title", title);
+    put(&mut open_graph, "og:description", description);
+    put(&mut open_graph, "og:image", image);
+    put(
+        &mut open_graph,
+        "og:image:alt",
+        image.and(article.photo_description.as_deref()),
+    );
+    put(&mut open_graph, "og:url", url.as_deref());
+    put(
+        &mut open_graph,
+        "article:published_time",
+        article.date_publication.as_deref(),
+    );
+    put(
+        &mut open_graph,
+        "article:modified_time",
+        article.updated_at.as_deref(),
+    );
+    put(
+        &mut open_graph,
+        "article:section",
+        article.categorie.as_deref(),
+    );
+
+    let card = if image.is_some() {
+        "summary_large_image"
+    } else {
+        "summary"
+    };
+    put(&mut twitter, "twitter:card", Some(card));
+    put(&mut twitter, "twitter:title", title);
+    put(&mut twitter, "twitter:description", description);
+    put(&mut twitter, "twitter:image", image);
+    put(
+        &mut twitter,
+        "twitter:image:alt",
+        image.and(article.photo_description.as_deref()),
+    );
+
+    SocialTags {
+        open_graph,
+        twitter,
+    }
+}
+
+/// Looks up an article for the metadata endpoints and fills its blank SEO
+/// fields the way `writes::apply_seo_defaults` does on save. A preview
+/// token also finds drafts.
+async fn seo_article(
+    state: &AppState,
+    route: &'static str,
+    slug: &str,
+    params: &ArticleQuery,
+) -> Result<Article, ApiError> {
+    let visibility = if params.is_preview(&state.config, slug)? {
+        live
+    } else {
+        published
+    };
+    let mut article = find_by_slug(
+        state,
+        route,
+        &state.collection,
+        slug,
+        visibility,
+        FindOneOptions::default(),
+    )
+    .await?
+    .ok_or_else(|| not_found(slug))?;
+    writes::apply_seo_defaults(&mut article, state.config.snippet_max_chars);
+    Ok(article)
+}
+
+/// Meta tags for the renderer to emit on the article page.
+pub async fn article_opengraph(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(params): Query<ArticleQuery>,
+) -> Result<Json<SocialTags>, ApiError> {
+    let slug = normalize_slug(&slug);
+    let article = seo_article(&state, "article_opengraph", &slug, &params).await?;
+    Ok(Json(social_tags(
+        &article,
+        state.config.article_base_url.as_deref(),
+    )))
+}
+
+/// What search results and social cards will show for an article.
+#[derive(Debug, Serialize)]
+pub struct SeoPreview {
+    slug: String,
+    seo_title: Option<String>,
+    seo_description: Option<String>,
+    /// `None` without `ARTICLE_BASE_URL`.
+    canonical_url: Option<String>,
+    #[serde(flatten)]
+    social: SocialTags,
+}
+
+/// The SEO fields as they'd be stored, plus the social cards built from
+/// them, so editors can check a draft before publishing it.
+pub async fn seo_preview(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(params): Query<ArticleQuery>,
+) -> Result<Json<SeoPreview>, ApiError> {
+    let slug = normalize_slug(&slug);
+    let article = seo_article(&state, "seo_preview", &slug, &params).await?;
+    let base_url = state.config.article_base_url.as_deref();
+
+    Ok(Json(SeoPreview {
+        social: social_tags(&article, base_url),
+        canonical_url: article_url(base_url, &article.slug),
+        slug: article.slug,
+        seo_title: article.seo_title,
+        seo_description: article.seo_description,
+    }))
+}
@@ -0,0 +1,213 @@
+//! Multi-part series: articles sharing a `series` slug, read in
+//! `series_order`.
+
+use crate::{
+    api_error, i18n::Message, internal_error, list_projection, normalize_slug, published, timed,
+    ApiError, AppState, Article, ArticleListItem,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use futures::TryStreamExt;
+use mongodb::{bson::doc, options::IndexOptions, Collection, IndexModel};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct SeriesResponse {
+    series: String,
+    articles: Vec<ArticleListItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesLink {
+    slug: String,
+    titre: String,
+}
+
+/// Where an article sits in its series, for "part 2 of 5" navigation.
+#[derive(Debug, Serialize)]
+pub struct SeriesInfo {
+    slug: String,
+    /// 1-based; `None` when the article itself isn't published yet.
+    position: Option<usize>,
+    total: usize,
+    prev: Option<SeriesLink>,
+    next: Option<SeriesLink>,
+}
+
+pub async fn ensure_indexes(collection: &Collection<Article>) -> mongodb::error::Result<()> {
+    let index = IndexModel::builder()
+        .keys(doc! { "series": 1, "series_order": 1 })
+        .options(IndexOptions::builder().sparse(true).build())
+        .build();
+    collection.create_index(index).await?;
+    Ok(())
+}
+
+/// Published articles of a series, capped at `MAX_AGGREGATION_RESULTS`.
+/// Articles without a `series_order` come last.
+async fn members(state: &AppState, series: &str) -> Result<Vec<Article>, ApiError> {
+    let filter = published(doc! { "series": series });
+    timed(state, "series", &filter, async {
+        state
+            .collection
+            .aggregate(vec![
+                doc! { "$match": filter.clone() },
+                doc! { "$addFields": {
+                    "_unordered": { "$eq": [{ "$type": "$series_order" }, "missing"] },
+                } },
+                doc! { "$sort": {
+                    "_unordered": 1,
+                    "series_order": 1,
+                    "date_publication": 1,
+                    "_id": 1,
+                } },
+                doc! { "$limit": state.config.max_aggregation_results as i64 },
+                doc! { "$project": list_projection() },
+            ])
+            .max_time(state.config.read_timeout)
+            .with_type::<Article>()
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)
+}
+
+pub async fn series_articles(
+    State(state): State<AppState>,
+    Path(series): Path<String>,
+) -> Result<Json<SeriesResponse>, ApiError> {
+    let series = normalize_slug(&series);
+    let articles = members(&state, &series).await?;
+    if articles.is_empty() {
+        return Err(api_error(
+            StatusCode::NOT_FOUND,
+            Message::SeriesNotFound { slug: series },
+        ));
+    }
+    Ok(Json(SeriesResponse {
+        series,
+        articles: articles.into_iter().map(ArticleListItem::from).collect(),
+    }))
+}
+
+/// Series navigation for `article`, or `None` when it isn't in a series.
+pub async fn series_info(
+    state: &AppState,
+    article: &Article,
+) -> Result<Option<SeriesInfo>, ApiError> {
+    let Some(series) = &article.series else {
+        return Ok(None);
+    };
+    let members = members(state, series).await?;
+    let position = members.iter().position(|m| m.slug == article.slug);
+    let link = |i: usize| {
+        members.get(i).map(|m| SeriesLink {
+            slug: m.slug.clone(),
+            titre: m.titre.clone(),
+        })
+    };
+    Ok(Some(SeriesInfo {
+        slug: series.clone(),
+        position: position.map(|i| i + 1),
+        total: members.len(),
+        prev: position.and_then(|i| i.checked_sub(1)).and_then(link),
+        next: position.and_then(|i| link(i + 1)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArticleResponse, ArticleStats, FullArticleResponse};
+    use serde::de::{Deserializer, IgnoredAny, MapAccess, Visitor};
+    use std::fmt;
+
+    /// Top-level keys of a JSON object, duplicates kept, which
+    /// `serde_json::Value` would silently collapse.
+    fn top_level_keys(json: &str) -> Vec<String> {
+        struct Keys;
+        impl<'de> Visitor<'de> for Keys {
+            type Value = Vec<String>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object")
+            }
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut keys = Vec::new();
+                while let Some((key, IgnoredAny)) = map.next_entry::<String, IgnoredAny>()? {
+                    keys.push(key);
+                }
+                Ok(keys)
+            }
+        }
+        serde_json::Deserializer::from_str(json)
+            .deserialize_map(Keys)
+            .unwrap()
+    }
+
+    fn assert_unique_keys(json: &str) {
+        let keys = top_level_keys(json);
+        let mut seen = std::collections::HashSet::new();
+        for key in &keys {
+            assert!(seen.insert(key), "duplicate key '{}' in {}", key, json);
+        }
+    }
+
+    fn article_in_series() -> Article {
+        serde_json::from_value(serde_json::json!({
+            "slug": "part-2",
+            "titre": "Part 2",
+            "series": "guide",
+            "series_order": 2,
+        }))
+        .unwrap()
+    }
+
+    fn nav() -> SeriesInfo {
+        let link = |slug: &str| SeriesLink {
+            slug: slug.to_string(),
+            titre: slug.to_string(),
+        };
+        SeriesInfo {
+            slug: "guide".to_string(),
+            position: Some(2),
+            total: 3,
+            prev: Some(link("part-1")),
+            next: Some(link("part-3")),
+        }
+    }
+
+    #[test]
+    fn article_response_keeps_series_slug_and_nav_apart() {
+        let json = serde_json::to_string(&ArticleResponse {
+            article: article_in_series(),
+            series_nav: Some(nav()),
+        })
+        .unwrap();
+        assert_unique_keys(&json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["series"], "guide");
+        assert_eq!(value["series_nav"]["position"], 2);
+    }
+
+    #[test]
+    fn full_article_response_keeps_series_slug_and_nav_apart() {
+        let article = article_in_series();
+        let json = serde_json::to_string(&FullArticleResponse {
+            stats: Some(ArticleStats::of(&article)),
+            related_articles: Some(Vec::new()),
+            series_nav: Some(nav()),
+            neighbors: None,
+            article,
+        })
+        .unwrap();
+        assert_unique_keys(&json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["series"], "guide");
+        assert_eq!(value["series_nav"]["next"]["slug"], "part-3");
+    }
+}
@@ -0,0 +1,237 @@
+//! Plain-text helpers for snippets built from `contenu`.
+
+/// Reduces HTML or Markdown content to plain text on a single line: tags,
+/// emphasis markers, headings, list markers and link targets are dropped,
+/// common entities decoded and whitespace collapsed.
+pub fn strip_markup(content: &str) -> String {
+    let without_tags = strip_tags(content);
+    let decoded = decode_entities(&without_tags);
+
+    let mut text = String::with_capacity(decoded.len());
+    for line in decoded.lines() {
+        text.push_str(&strip_markdown_line(line));
+        text.push(' ');
+    }
+    collapse_whitespace(&text)
+}
+
+/// Shortens `text` to at most `max_chars` characters, ellipsis included.
+/// Counts and cuts characters, never bytes, so accented letters can't be
+/// split; the cut moves back to the last word boundary when there is one.
+/// Use it for every length limit on displayed text.
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    // Not even the ellipsis fits.
+    if max_chars == 0 {
+        return String::new();
+    }
+    // Leave room for the ellipsis.
+    let budget = max_chars.saturating_sub(1);
+    let end = text
+        .char_indices()
+        .nth(budget)
+        .map_or(text.len(), |(i, _)| i);
+    let cut = &text[..end];
+    // Only back up when the cut fell inside a word.
+    let mid_word = !text[end..].starts_with(char::is_whitespace);
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(i) if i > 0 && mid_word => &cut[..i],
+        _ => cut,
+    };
+    format!(
+        "{}…",
+        cut.trim_end_matches(|c: char| c.is_whitespace() || ",;:".contains(c))
+    )
+}
+
+/// Images in HTML or Markdown content: `<img>` tags and `![alt](url)`.
+pub fn count_images(content: &str) -> usize {
+    count_tags(content, "img") + content.matches("![").count()
+}
+
+/// Links in HTML or Markdown content: `<a>` tags and `[text](url)`, not
+/// counting Markdown images.
+pub fn count_links(content: &str) -> usize {
+    let markdown = content
+        .matches("](")
+        .count()
+        .saturating_sub(content.matches("![").count());
+    count_tags(content, "a") + markdown
+}
+
+/// Opening `<name ...>` tags, case-insensitively.
+fn count_tags(content: &str, name: &str) -> usize {
+    let lower = content.to_ascii_lowercase();
+    let open = format!("<{}", name);
+    lower
+        .match_indices(&open)
+        .filter(|(i, _)| {
+            lower[i + open.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| c == '>' || c == '/' || c.is_whitespace())
+        })
+        .count()
+}
+
+/// `petit_description` when present, otherwise a plain-text snippet of
+/// `contenu`.
+pub fn description_or_snippet(
+    description: Option<&str>,
+    contenu: Option<&str>,
+    max_chars: usize,
+) -> Option<String> {
+    let source = match description.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(d) => d.to_string(),
+        None => strip_markup(contenu?),
+    };
+    Some(truncate_chars(&source, max_chars)).filter(|s| !s.is_empty())
+}
+
+fn strip_tags(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        let starts_tag = c == '<'
+            && chars
+                .peek()
+                .is_some_and(|n| n.is_ascii_alphabetic() || *n == '/' || *n == '!');
+        if starts_tag {
+            for c in chars.by_ref() {
+                if c == '>' {
+                    break;
+                }
+            }
+            // Tags often separate words (`<br>`, `</p><p>`).
+            out.push(' ');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn strip_markdown_line(line: &str) -> String {
+    let mut line = line.trim_start();
+    line = line
+        .trim_start_matches('#')
+        .trim_start_matches('>')
+        .trim_start();
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            line = rest;
+            break;
+        }
+    }
+
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // `![alt](url)` and `[text](url)` keep only the visible text.
+            '!' if chars.peek() == Some(&'[') => {}
+            '[' => {
+                let mut label = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    label.push(c);
+                }
+                if chars.peek() == Some(&'(') {
+                    for c in chars.by_ref() {
+                        if c == ')' {
+                            break;
+                        }
+                    }
+                }
+                out.push_str(&label);
+            }
+            '*' | '`' | '~' => {}
+            '_' if chars.peek() == Some(&'_') => {
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_chars_keeps_short_text() {
+        assert_eq!(truncate_chars("  déjà vu ", 7), "déjà vu");
+        assert_eq!(truncate_chars("", 0), "");
+    }
+
+    #[test]
+    fn truncate_chars_to_zero_is_empty() {
+        assert_eq!(truncate_chars("texte", 0), "");
+    }
+
+    #[test]
+    fn truncate_chars_cuts_accented_text_on_characters() {
+        // The cut falls inside "approche": back to the previous word.
+        let cut = truncate_chars("L'été approche déjà", 10);
+        assert_eq!(cut, "L'été…");
+        // No word boundary: cut between two multi-byte characters.
+        let cut = truncate_chars("éééééé", 4);
+        assert_eq!(cut, "ééé…");
+        assert_eq!(cut.chars().count(), 4);
+        assert_eq!(truncate_chars("été", 1), "…");
+    }
+
+    #[test]
+    fn truncate_chars_drops_trailing_punctuation() {
+        assert_eq!(truncate_chars("Bonjour, et bienvenue", 10), "Bonjour…");
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    internal_error, list_projection, published, timed, ApiError, AppState, Article,
+    ArticleListItem, ListResponse, Page, Paginated,
+};
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, DateTime, Document},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+pub const VIEWS_COLLECTION: &str = "article_views";
+
+/// One read of an article. Kept for `VIEW_RETENTION_HOURS` by a TTL index,
+/// which bounds the longest trending window.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArticleView {
+    slug: String,
+    viewed_at: DateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingQuery {
+    page: Option<u64>,
+    limit: Option<i64>,
+    /// Window size; defaults to `TRENDING_WINDOW_HOURS`.
+    hours: Option<u64>,
+}
+
+pub async fn ensure_indexes(
+    views: &Collection<ArticleView>,
+    retention: Duration,
+) -> mongodb::error::Result<()> {
+    let ttl = IndexModel::builder()
+        .keys(doc! { "viewed_at": 1 })
+        .options(IndexOptions::builder().expire_after(retention).build())
+        .build();
+    views.create_index(ttl).await?;
+    Ok(())
+}
+
+/// Records a view without holding up the response; a lost view only skews
+/// trending slightly.
+pub fn record_view(state: &AppState, slug: &str) {
+    let views = state.views.clone();
+    let view = ArticleView {
+        slug: slug.to_string(),
+        viewed_at: DateTime::now(),
+    };
+    tokio::spawn(async move {
+        if let Err(e) = views.insert_one(&view).await {
+            tracing::warn!("Failed to record view for '{}': {}", view.slug, e);
+        }
+    });
+}
+
+/// Articles ranked by views within the last `hours`, most viewed first.
+pub async fn trending_articles(
+    State(state): State<AppState>,
+    Query(params): Query<TrendingQuery>,
+) -> Result<Json<ListResponse>, ApiError> {
+    let paging = Page::new(params.page, params.limit, &state.config)?;
+    let Page { limit, skip, .. } = paging;
+    let hours = params
+        .hours
+        .unwrap_or(state.config.trending_window_hours)
+        .clamp(1, state.config.view_retention_hours);
+    let since = Utc::now() - chrono::Duration::hours(hours as i64);
+    let since = DateTime::from_millis(since.timestamp_millis());
+
+    let pipeline = vec![
+        doc! { "$match": { "viewed_at": { "$gte": since } } },
+        doc! { "$group": { "_id": "$slug", "views": { "$sum": 1 } } },
+        doc! { "$sort": { "views": -1, "_id": 1 } },
+        doc! { "$facet": {
+            "total": [{ "$count": "count" }],
+            "page": [{ "$skip": skip as i64 }, { "$limit": limit }],
+        } },
+    ];
+
+    let first_stage = pipeline[0].clone();
+    let result: Option<Document> = timed(&state, "trending_articles", &first_stage, async {
+        state
+            .views
+            .aggregate(pipeline)
+            .max_time(state.config.read_timeout)
+            .await?
+            .try_next()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+    let result = result.unwrap_or_default();
+
+    let total = result
+        .get_array("total")
+        .ok()
+        .and_then(|t| t.first())
+        .and_then(|t| t.as_document())
+        .and_then(|t| match t.get("count") {
+            Some(Bson::Int32(n)) => Some(*n as u64),
+            Some(Bson::Int64(n)) => Some(*n as u64),
+            _ => None,
+        })
+        .unwrap_or(0);
+    let ranked: Vec<String> = result
+        .get_array("page")
+        .map(|p| {
+            p.iter()
+                .filter_map(|d| d.as_document()?.get_str("_id").ok().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let filter = published(doc! { "slug": { "$in": &ranked } });
+    let articles: Vec<Article> = timed(&state, "trending_articles", &filter, async {
+        state
+            .collection
+            .find(filter.clone())
+            .max_time(state.config.read_timeout)
+            .projection(list_projection())
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+
+    // Restore the ranking; slugs whose article was removed are dropped.
+    let mut by_slug: HashMap<String, Article> =
+        articles.into_iter().map(|a| (a.slug.clone(), a)).collect();
+    let items = ranked
+        .iter()
+        .filter_map(|slug| by_slug.remove(slug))
+        .map(ArticleListItem::from)
+        .collect();
+
+    Ok(Json(Paginated::new(items, total, paging)))
+}
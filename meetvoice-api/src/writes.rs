@@ -0,0 +1,1348 @@
+use crate::{
+    api_error, bad_request,
+    config::Config,
+    date_range, escape_regex,
+    i18n::{Lang, Message},
+    internal_error, is_blank, live, normalize_slug, not_found, parse_date_param, text, timed,
+    ApiError, AppState, Article, ArticleStatus, ErrorResponse, FieldError,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc, Document, Regex},
+    options::{ReturnDocument, UpdateModifications},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+pub const MAX_SEO_KEYWORDS: usize = 20;
+pub const MAX_SEO_KEYWORD_CHARS: usize = 50;
+pub const MAX_TAGS: usize = 20;
+pub const MAX_TAG_CHARS: usize = 50;
+pub const MAX_RELATED: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct WriteQuery {
+    /// Fill blank SEO fields from the content (default). Pass `false` to
+    /// store the SEO fields exactly as sent.
+    auto_seo: Option<bool>,
+}
+
+/// Cleans up editor input before validation: trims the slug and keywords
+/// and drops keywords repeated with different casing (first one wins).
+/// Tags and related slugs are trimmed, lowercased and deduplicated; the
+/// author is trimmed.
+pub fn normalize_article(article: &mut Article) {
+    article.slug = article.slug.trim().to_string();
+    if let Some(author) = &mut article.author {
+        *author = author.trim().to_string();
+    }
+    if let Some(series) = &mut article.series {
+        *series = series.trim().to_lowercase();
+    }
+    if let Some(tags) = &mut article.tags {
+        let mut seen = HashSet::new();
+        *tags = tags
+            .iter()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| seen.insert(t.clone()))
+            .collect();
+    }
+    if let Some(related) = &mut article.related {
+        let mut seen = HashSet::new();
+        *related = related
+            .iter()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| seen.insert(s.clone()))
+            .collect();
+    }
+    if let Some(keywords) = &mut article.seo_keywords {
+        let mut seen = HashSet::new();
+        *keywords = keywords
+            .iter()
+            .map(|k| k.trim().to_string())
+            .filter(|k| seen.insert(k.to_lowercase()))
+            .collect();
+    }
+}
+
+fn is_slug(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Checks a normalized article; see `normalize_article`.
+pub fn validate_article(article: &Article, config: &Config) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if article.slug.is_empty() || !is_slug(&article.slug) {
+        errors.push(FieldError {
+            field: "slug",
+            message: "must be non-empty lowercase letters, digits and hyphens".to_string(),
+        });
+    }
+    if let Some(series) = &article.series {
+        if series.is_empty() || !is_slug(series) {
+            errors.push(FieldError {
+                field: "series",
+                message: "must be non-empty lowercase letters, digits and hyphens".to_string(),
+            });
+        }
+    }
+    if article.series_order.is_some_and(|o| o < 0) {
+        errors.push(FieldError {
+            field: "series_order",
+            message: "must not be negative".to_string(),
+        });
+    }
+    if article.titre.trim().is_empty() {
+        errors.push(FieldError {
+            field: "titre",
+            message: "must not be empty".to_string(),
+        });
+    }
+    if let Some(date) = &article.date_publication {
+        if parse_date_param("date_publication", date).is_err() {
+            errors.push(FieldError {
+                field: "date_publication",
+                message: "must be YYYY-MM-DD or RFC 3339".to_string(),
+            });
+        }
+    }
+    if let Some(keywords) = &article.seo_keywords {
+        if keywords.len() > MAX_SEO_KEYWORDS {
+            errors.push(FieldError {
+                field: "seo_keywords",
+                message: format!(
+                    "at most {} distinct keywords allowed, got {}",
+                    MAX_SEO_KEYWORDS,
+                    keywords.len()
+                ),
+            });
+        }
+        for keyword in keywords {
+            let len = keyword.chars().count();
+            if len == 0 || len > MAX_SEO_KEYWORD_CHARS {
+                errors.push(FieldError {
+                    field: "seo_keywords",
+                    message: format!(
+                        "keyword '{}' must be 1 to {} characters",
+                        keyword, MAX_SEO_KEYWORD_CHARS
+                    ),
+                });
+            }
+        }
+    }
+    // Anything but a draft is visible once its date comes, so it needs a
+    // real body; the length counts text, not markup.
+    if article.status != Some(ArticleStatus::Draft) {
+        let min = config.min_published_content_chars;
+        let len = article
+            .contenu
+            .as_deref()
+            .map_or(0, |c| text::strip_markup(c).trim().chars().count());
+        if len < min {
+            errors.push(FieldError {
+                field: "contenu",
+                message: format!(
+                    "must have at least {} characters of text unless status is draft, got {}",
+                    min, len
+                ),
+            });
+        }
+    }
+    if let Some(related) = &article.related {
+        if related.len() > MAX_RELATED {
+            errors.push(FieldError {
+                field: "related",
+                message: format!("at most {} related articles allowed", MAX_RELATED),
+            });
+        }
+        for slug in related {
+            if slug.is_empty() || !is_slug(slug) || *slug == article.slug {
+                errors.push(FieldError {
+                    field: "related",
+                    message: format!(
+                        "'{}' must be another article's slug: lowercase letters, digits \
+                         and hyphens",
+                        slug
+                    ),
+                });
+            }
+        }
+    }
+    if let Some(tags) = &article.tags {
+        if tags.len() > MAX_TAGS {
+            errors.push(FieldError {
+                field: "tags",
+                message: format!(
+                    "at most {} distinct tags allowed, got {}",
+                    MAX_TAGS,
+                    tags.len()
+                ),
+            });
+        }
+        for tag in tags {
+            let len = tag.chars().count();
+            if len == 0 || len > MAX_TAG_CHARS {
+                errors.push(FieldError {
+                    field: "tags",
+                    message: format!("tag '{}' must be 1 to {} characters", tag, MAX_TAG_CHARS),
+                });
+            }
+        }
+    }
+    errors
+}
+
+fn validation_error(fields: Vec<FieldError>) -> ApiError {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(ErrorResponse {
+            error: Message::ValidationFailed.text(Lang::current()),
+            fields,
+        }),
+    )
+}
+
+/// Fields computed from the rest of the article rather than edited
+/// directly; `POST /admin/reindex` backfills them.
+pub const DERIVED_FIELDS: &[&str] = &["seo_title", "seo_description"];
+
+/// Fields `normalize_article` may rewrite, besides the slug.
+pub const NORMALIZED_FIELDS: &[&str] = &["author", "series", "tags", "related", "seo_keywords"];
+
+/// Brings a stored article up to what a write would store today:
+/// normalized, and with every derived field recomputed (see
+/// `DERIVED_FIELDS`) unless it was saved with `?auto_seo=false`.
+pub fn derive_fields(article: &mut Article, config: &Config) {
+    normalize_article(article);
+    if article.auto_seo != Some(false) {
+        apply_seo_defaults(article, config.snippet_max_chars);
+    }
+}
+
+/// Fills blank `seo_title`/`seo_description` from `titre` and
+/// `petit_description` (or a plain-text snippet of `contenu` when that is
+/// blank too). Values provided by the editor are never replaced.
+pub fn apply_seo_defaults(article: &mut Article, max_description_chars: usize) {
+    if is_blank(&article.seo_title) {
+        article.seo_title = Some(article.titre.trim().to_string());
+    }
+    if is_blank(&article.seo_description) {
+        article.seo_description = text::description_or_snippet(
+            article.petit_description.as_deref(),
+            article.contenu.as_deref(),
+            max_description_chars,
+        );
+    }
+}
+
+/// Validates the article and applies derived fields before it is stored.
+fn prepare(article: &mut Article, query: &WriteQuery, config: &Config) -> Result<(), ApiError> {
+    article.id = None;
+    article.deleted_at = None;
+    normalize_article(article);
+    let errors = validate_article(article, config);
+    if !errors.is_empty() {
+        return Err(validation_error(errors));
+    }
+    let auto_seo = query.auto_seo.unwrap_or(true);
+    if auto_seo {
+        apply_seo_defaults(article, config.snippet_max_chars);
+    }
+    // Remembered for `admin::reindex`; never taken from the body.
+    article.auto_seo = (!auto_seo).then_some(false);
+    article.updated_at = Some(now_timestamp());
+    Ok(())
+}
+
+/// Whether any article, soft-deleted ones included, uses `slug`.
+async fn slug_taken(state: &AppState, slug: &str) -> Result<bool, ApiError> {
+    let filter = doc! { "slug": slug };
+    let existing = timed(
+        state,
+        "slug_taken",
+        &filter,
+        state
+            .collection
+            .find_one(filter.clone())
+            .max_time(state.config.read_timeout),
+    )
+    .await
+    .map_err(internal_error)?;
+    Ok(existing.is_some())
+}
+
+async fn ensure_slug_free(state: &AppState, slug: &str) -> Result<(), ApiError> {
+    if slug_taken(state, slug).await? {
+        return Err(api_error(
+            StatusCode::CONFLICT,
+            Message::ArticleExists {
+                slug: slug.to_string(),
+            },
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationResponse {
+    valid: bool,
+    /// Problems that don't block a save by themselves.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<FieldError>,
+}
+
+/// Dry run of the checks `POST`/`PUT` apply, without writing anything.
+/// A taken slug is only a warning: it may be the article being edited, and
+/// it can change before the actual save anyway.
+pub async fn validate_payload(
+    State(state): State<AppState>,
+    Json(mut article): Json<Article>,
+) -> Result<Json<ValidationResponse>, ApiError> {
+    normalize_article(&mut article);
+    let errors = validate_article(&article, &state.config);
+    if !errors.is_empty() {
+        return Err(validation_error(errors));
+    }
+
+    let mut warnings = Vec::new();
+    if slug_taken(&state, &article.slug).await? {
+        warnings.push(FieldError {
+            field: "slug",
+            message: format!("'{}' is already used by another article", article.slug),
+        });
+    }
+    Ok(Json(ValidationResponse {
+        valid: true,
+        warnings,
+    }))
+}
+
+pub async fn create_article(
+    State(state): State<AppState>,
+    Query(query): Query<WriteQuery>,
+    Json(mut article): Json<Article>,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<Article>), ApiError> {
+    prepare(&mut article, &query, &state.config)?;
+    ensure_slug_free(&state, &article.slug).await?;
+
+    let filter = doc! { "slug": &article.slug };
+    let result = timed(
+        &state,
+        "create_article",
+        &filter,
+        state.collection.insert_one(&article),
+    )
+    .await
+    .map_err(internal_error)?;
+    article.id = result.inserted_id.as_object_id();
+    state.list_cache.invalidate(&article);
+
+    let location = format!("{}/articles/{}", state.config.api_prefix, article.slug);
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, location)],
+        Json(article),
+    ))
+}
+
+/// Timestamp format for `updated_at`: fixed width UTC so stored values
+/// sort and compare as strings.
+pub fn now_timestamp() -> String {
+    Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+/// Upper bound on `updated_at` from an `If-Unmodified-Since` header, or
+/// `None` when the header is absent or not a valid HTTP-date (which
+/// RFC 9110 says to ignore). HTTP-dates have second precision, so anything
+/// within the named second still counts as unmodified.
+fn unmodified_since(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(header::IF_UNMODIFIED_SINCE)?.to_str().ok()?;
+    let since = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    let bound = since + chrono::Duration::seconds(1);
+    Some(bound.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+}
+
+/// Articles written before `updated_at` existed count as unmodified.
+fn is_unmodified(updated_at: &Option<String>, bound: &str) -> bool {
+    !matches!(updated_at.as_deref(), Some(u) if u >= bound)
+}
+
+fn precondition_failed(slug: &str) -> ApiError {
+    api_error(
+        StatusCode::PRECONDITION_FAILED,
+        Message::ModifiedSince {
+            slug: slug.to_string(),
+        },
+    )
+}
+
+/// Refuses the write with `412` when `If-Unmodified-Since` names a time
+/// before `stored` was last written.
+fn check_unmodified(headers: &HeaderMap, stored: &Article) -> Result<(), ApiError> {
+    match unmodified_since(headers) {
+        Some(bound) if !is_unmodified(&stored.updated_at, &bound) => {
+            Err(precondition_failed(&stored.slug))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn found(slug: &str, article: Option<Article>) -> Result<Article, ApiError> {
+    article.ok_or_else(|| not_found(slug))
+}
+
+/// A conditional replace that matched nothing lost a race with another
+/// write.
+fn replaced(stored: &Article, matched_count: u64) -> Result<(), ApiError> {
+    if matched_count == 0 {
+        return Err(precondition_failed(&stored.slug));
+    }
+    Ok(())
+}
+
+pub async fn find_article(state: &AppState, slug: &str) -> Result<Article, ApiError> {
+    let filter = live(doc! { "slug": slug });
+    let article = timed(
+        state,
+        "find_article",
+        &filter,
+        state
+            .collection
+            .find_one(filter.clone())
+            .max_time(state.config.read_timeout),
+    )
+    .await
+    .map_err(internal_error)?;
+    found(slug, article)
+}
+
+/// Replaces `stored` with `article` unless it changed in the meantime, in
+/// which case the write is refused with `412`.
+pub async fn replace_if_unchanged(
+    state: &AppState,
+    stored: &Article,
+    article: &mut Article,
+) -> Result<(), ApiError> {
+    let filter = doc! { "_id": stored.id, "updated_at": &stored.updated_at };
+    let result = timed(
+        state,
+        "replace_if_unchanged",
+        &filter,
+        state.collection.replace_one(filter.clone(), &*article),
+    )
+    .await
+    .map_err(internal_error)?;
+    replaced(stored, result.matched_count)?;
+    article.id = stored.id;
+    Ok(())
+}
+
+/// Replaces the article stored under `slug`. The body may carry a new slug
+/// as long as it isn't taken. With `If-Unmodified-Since`, the write only
+/// applies if the article hasn't changed since that time.
+pub async fn update_article(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<WriteQuery>,
+    headers: HeaderMap,
+    Json(mut article): Json<Article>,
+) -> Result<Json<Article>, ApiError> {
+    let slug = normalize_slug(&slug);
+    prepare(&mut article, &query, &state.config)?;
+    if article.slug != slug {
+        ensure_slug_free(&state, &article.slug).await?;
+    }
+
+    let stored = find_article(&state, &slug).await?;
+    check_unmodified(&headers, &stored)?;
+    // The weight has its own endpoint; a body without one keeps it.
+    if article.sort_weight.is_none() {
+        article.sort_weight = stored.sort_weight;
+    }
+    if article.old_slugs.is_none() {
+        article.old_slugs = stored.old_slugs.clone();
+    }
+    replace_if_unchanged(&state, &stored, &mut article).await?;
+    state.list_cache.invalidate(&stored);
+    state.list_cache.invalidate(&article);
+    Ok(Json(article))
+}
+
+/// Fields a `PATCH` may change; absent fields are left as stored.
+#[derive(Debug, Deserialize)]
+pub struct ArticlePatch {
+    titre: Option<String>,
+    petit_description: Option<String>,
+    contenu: Option<String>,
+    theme: Option<String>,
+    categorie: Option<String>,
+    photo: Option<String>,
+    photo_description: Option<String>,
+    photo_highlight: Option<String>,
+    date_publication: Option<String>,
+    seo_title: Option<String>,
+    seo_description: Option<String>,
+    seo_keywords: Option<Vec<String>>,
+    author: Option<String>,
+    tags: Option<Vec<String>>,
+    series: Option<String>,
+    series_order: Option<i32>,
+    related: Option<Vec<String>>,
+    status: Option<ArticleStatus>,
+}
+
+impl ArticlePatch {
+    fn apply(self, article: &mut Article) {
+        fn set<T>(target: &mut Option<T>, value: Option<T>) {
+            if value.is_some() {
+                *target = value;
+            }
+        }
+        if let Some(titre) = self.titre {
+            article.titre = titre;
+        }
+        set(&mut article.petit_description, self.petit_description);
+        set(&mut article.contenu, self.contenu);
+        set(&mut article.theme, self.theme);
+        set(&mut article.categorie, self.categorie);
+        set(&mut article.photo, self.photo);
+        set(&mut article.photo_description, self.photo_description);
+        set(&mut article.photo_highlight, self.photo_highlight);
+        set(&mut article.date_publication, self.date_publication);
+        set(&mut article.seo_title, self.seo_title);
+        set(&mut article.seo_description, self.seo_description);
+        set(&mut article.seo_keywords, self.seo_keywords);
+        set(&mut article.author, self.author);
+        set(&mut article.tags, self.tags);
+        set(&mut article.series, self.series);
+        set(&mut article.series_order, self.series_order);
+        set(&mut article.related, self.related);
+        set(&mut article.status, self.status);
+    }
+}
+
+/// Partial update. Honors `If-Unmodified-Since` like `PUT`.
+pub async fn patch_article(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<WriteQuery>,
+    headers: HeaderMap,
+    Json(patch): Json<ArticlePatch>,
+) -> Result<Json<Article>, ApiError> {
+    let slug = normalize_slug(&slug);
+    let stored = find_article(&state, &slug).await?;
+    check_unmodified(&headers, &stored)?;
+
+    let mut article = stored.clone();
+    patch.apply(&mut article);
+    prepare(&mut article, &query, &state.config)?;
+    replace_if_unchanged(&state, &stored, &mut article).await?;
+    state.list_cache.invalidate(&stored);
+    state.list_cache.invalidate(&article);
+    Ok(Json(article))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeightRequest {
+    /// `null` (or omitted) clears the weight.
+    sort_weight: Option<i32>,
+}
+
+/// Sets or clears the manual ordering weight; see `SortBy::Weight`.
+pub async fn set_weight(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(request): Json<WeightRequest>,
+) -> Result<Json<Article>, ApiError> {
+    let slug = normalize_slug(&slug);
+    let filter = live(doc! { "slug": &slug });
+    let update = doc! { "$set": {
+        "sort_weight": request.sort_weight,
+        "updated_at": now_timestamp(),
+    } };
+    let article = timed(
+        &state,
+        "set_weight",
+        &filter,
+        state
+            .collection
+            .find_one_and_update(filter.clone(), update)
+            .return_document(ReturnDocument::After),
+    )
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| not_found(&slug))?;
+    state.list_cache.invalidate(&article);
+    Ok(Json(article))
+}
+
+/// Soft-deletes an article: it disappears from every read but stays in the
+/// collection, and its slug stays taken.
+pub async fn delete_article(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let slug = normalize_slug(&slug);
+    let filter = live(doc! { "slug": &slug });
+    let now = now_timestamp();
+    let update = doc! { "$set": { "deleted_at": &now, "updated_at": &now } };
+    let deleted = timed(
+        &state,
+        "delete_article",
+        &filter,
+        state.collection.find_one_and_update(filter.clone(), update),
+    )
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| not_found(&slug))?;
+    state.list_cache.invalidate(&deleted);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Articles a bulk write applies to. Unlike the listing filters, `theme`
+/// matches exactly.
+#[derive(Debug, Deserialize)]
+pub struct BulkFilter {
+    categorie: Option<String>,
+    theme: Option<String>,
+    /// Inclusive publication date bounds.
+    date_from: Option<String>,
+    date_to: Option<String>,
+}
+
+impl BulkFilter {
+    /// The live articles matched. At least one criterion is required so a
+    /// bare request can't touch the whole collection.
+    fn to_filter(&self) -> Result<Document, ApiError> {
+        let mut criteria = doc! {};
+        if let Some(categorie) = self.categorie.as_ref().filter(|c| !c.trim().is_empty()) {
+            criteria.insert("categorie", categorie);
+        }
+        if let Some(theme) = self.theme.as_ref().filter(|t| !t.trim().is_empty()) {
+            criteria.insert("theme", theme);
+        }
+        let from = self
+            .date_from
+            .as_deref()
+            .map(|d| parse_date_param("date_from", d))
+            .transpose()?;
+        if let Some(range) = date_range(from, self.date_to.as_deref())? {
+            criteria.insert("date_publication", range);
+        }
+        if criteria.is_empty() {
+            return Err(bad_request(Message::EmptyFilter));
+        }
+        Ok(live(criteria))
+    }
+}
+
+/// Articles to soft-delete with `POST /articles/bulk-delete`.
+#[derive(Debug, Deserialize)]
+pub struct BulkDeleteRequest {
+    #[serde(flatten)]
+    filter: BulkFilter,
+    /// Must be `true`; guards against accidental calls.
+    #[serde(default)]
+    confirm: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkDeleteResponse {
+    deleted: u64,
+}
+
+/// Soft-deletes every article matching the filter; see `BulkFilter`.
+pub async fn bulk_delete(
+    State(state): State<AppState>,
+    Json(request): Json<BulkDeleteRequest>,
+) -> Result<Json<BulkDeleteResponse>, ApiError> {
+    if !request.confirm {
+        return Err(bad_request(Message::ConfirmRequired));
+    }
+
+    let filter = request.filter.to_filter()?;
+    let now = now_timestamp();
+    let update = doc! { "$set": { "deleted_at": &now, "updated_at": &now } };
+    let result = timed(
+        &state,
+        "bulk_delete",
+        &filter,
+        state.collection.update_many(filter.clone(), update),
+    )
+    .await
+    .map_err(internal_error)?;
+    tracing::info!(deleted = result.modified_count, filter = %filter, "Bulk soft-delete");
+    state.list_cache.clear();
+
+    Ok(Json(BulkDeleteResponse {
+        deleted: result.modified_count,
+    }))
+}
+
+/// An import item that wasn't stored, by position in the request.
+#[derive(Debug, Serialize)]
+pub struct BulkRejection {
+    index: usize,
+    slug: String,
+    #[serde(flatten)]
+    error: ErrorResponse,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BulkCreateResponse {
+    created: Vec<String>,
+    updated: Vec<String>,
+    unchanged: Vec<String>,
+    rejected: Vec<BulkRejection>,
+}
+
+/// What `POST /articles/bulk` does with one item.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportAction {
+    Create,
+    Update,
+    Unchanged,
+    Invalid,
+}
+
+/// One prepared import item and what the import does with it, shared by
+/// `bulk_create` and `import_dry_run` so the dry run can't drift from the
+/// import.
+struct PlannedItem {
+    index: usize,
+    article: Article,
+    /// For updates, the article being replaced.
+    stored: Option<Article>,
+    outcome: Result<ImportAction, ErrorResponse>,
+}
+
+impl PlannedItem {
+    fn action(&self) -> ImportAction {
+        self.outcome.as_ref().map_or(ImportAction::Invalid, |a| *a)
+    }
+}
+
+fn check_batch_size(articles: &[Article], config: &Config) -> Result<(), ApiError> {
+    let max = config.max_batch_size;
+    if articles.len() > max {
+        return Err(bad_request(Message::BatchTooLarge { max }));
+    }
+    Ok(())
+}
+
+/// The stored articles, soft-deleted ones included, sharing a slug with an
+/// import item.
+async fn stored_for_import(
+    state: &AppState,
+    route: &'static str,
+    articles: &[Article],
+) -> Result<HashMap<String, Article>, ApiError> {
+    // Trimmed as `normalize_article` will store them.
+    let slugs: Vec<&str> = articles.iter().map(|a| a.slug.trim()).collect();
+    let filter = doc! { "slug": { "$in": &slugs } };
+    let existing: Vec<Article> = timed(state, route, &filter, async {
+        state
+            .collection
+            .find(filter.clone())
+            .max_time(state.config.long_timeout)
+            .await?
+            .try_collect()
+            .await
+    })
+    .await
+    .map_err(internal_error)?;
+    Ok(existing.into_iter().map(|a| (a.slug.clone(), a)).collect())
+}
+
+/// Prepares each item like `POST /articles` and classifies it against
+/// `stored`. An existing article is an update unless the prepared item
+/// would store the same fields, ignoring `updated_at`; a soft-deleted match
+/// is always an update, which restores it. A slug repeated within the batch
+/// is invalid.
+fn plan_import(
+    articles: Vec<Article>,
+    stored: &HashMap<String, Article>,
+    query: &WriteQuery,
+    config: &Config,
+) -> Vec<PlannedItem> {
+    let mut seen = HashSet::new();
+    let mut plan = Vec::with_capacity(articles.len());
+    for (index, mut article) in articles.into_iter().enumerate() {
+        let prepared = prepare(&mut article, query, config).and_then(|()| {
+            if seen.insert(article.slug.clone()) {
+                Ok(())
+            } else {
+                Err(api_error(
+                    StatusCode::CONFLICT,
+                    Message::ArticleExists {
+                        slug: article.slug.clone(),
+                    },
+                ))
+            }
+        });
+        let current = prepared
+            .is_ok()
+            .then(|| stored.get(&article.slug))
+            .flatten();
+        if let Some(current) = current {
+            // Like `PUT`, fields with their own endpoints are kept.
+            if article.sort_weight.is_none() {
+                article.sort_weight = current.sort_weight;
+            }
+            if article.old_slugs.is_none() {
+                article.old_slugs = current.old_slugs.clone();
+            }
+        }
+        let outcome = match prepared {
+            Err((_, Json(error))) => Err(error),
+            Ok(()) => Ok(match current {
+                None => ImportAction::Create,
+                Some(current) if same_content(current, &article) => ImportAction::Unchanged,
+                Some(_) => ImportAction::Update,
+            }),
+        };
+        plan.push(PlannedItem {
+            index,
+            article,
+            stored: current.cloned(),
+            outcome,
+        });
+    }
+    plan
+}
+
+/// Imports up to `MAX_BATCH_SIZE` articles in one call. Each item is
+/// classified by `plan_import`: new slugs are inserted, existing articles
+/// replaced and identical ones left alone. Invalid items, and updates that
+/// lose a race with another write (`412`), are reported instead of failing
+/// the whole request. Accepts `Content-Encoding: gzip`.
+pub async fn bulk_create(
+    State(state): State<AppState>,
+    Query(query): Query<WriteQuery>,
+    Json(articles): Json<Vec<Article>>,
+) -> Result<Json<BulkCreateResponse>, ApiError> {
+    check_batch_size(&articles, &state.config)?;
+    let stored = stored_for_import(&state, "bulk_create", &articles).await?;
+
+    let mut response = BulkCreateResponse::default();
+    let mut to_insert = Vec::new();
+    let mut to_replace = Vec::new();
+    for item in plan_import(articles, &stored, &query, &state.config) {
+        match item.outcome {
+            Ok(ImportAction::Create) => to_insert.push(item.article),
+            Ok(ImportAction::Update) => to_replace.push(item),
+            Ok(_) => response.unchanged.push(item.article.slug),
+            Err(error) => response.rejected.push(BulkRejection {
+                index: item.index,
+                slug: item.article.slug,
+                error,
+            }),
+        }
+    }
+
+    if !to_insert.is_empty() {
+        let slugs: Vec<&str> = to_insert.iter().map(|a| a.slug.as_str()).collect();
+        let filter = doc! { "slug": { "$in": slugs } };
+        timed(
+            &state,
+            "bulk_create",
+            &filter,
+            state.collection.insert_many(&to_insert),
+        )
+        .await
+        .map_err(internal_error)?;
+        for article in &to_insert {
+            state.list_cache.invalidate(article);
+        }
+    }
+    response.created = to_insert.into_iter().map(|a| a.slug).collect();
+
+    for PlannedItem {
+        index,
+        mut article,
+        stored,
+        ..
+    } in to_replace
+    {
+        let Some(stored) = stored else { continue };
+        match replace_if_unchanged(&state, &stored, &mut article).await {
+            Ok(()) => {
+                state.list_cache.invalidate(&stored);
+                state.list_cache.invalidate(&article);
+                response.updated.push(article.slug);
+            }
+            Err((status, Json(error))) if status == StatusCode::PRECONDITION_FAILED => {
+                response.rejected.push(BulkRejection {
+                    index,
+                    slug: article.slug,
+                    error,
+                })
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    response.rejected.sort_by_key(|r| r.index);
+
+    Ok(Json(response))
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunItem {
+    index: usize,
+    slug: String,
+    action: ImportAction,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorResponse>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DryRunCounts {
+    create: usize,
+    update: usize,
+    unchanged: usize,
+    invalid: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunResponse {
+    counts: DryRunCounts,
+    items: Vec<DryRunItem>,
+}
+
+impl DryRunResponse {
+    fn new(plan: Vec<PlannedItem>) -> Self {
+        let mut counts = DryRunCounts::default();
+        let mut items = Vec::with_capacity(plan.len());
+        for item in plan {
+            let action = item.action();
+            match action {
+                ImportAction::Create => counts.create += 1,
+                ImportAction::Update => counts.update += 1,
+                ImportAction::Unchanged => counts.unchanged += 1,
+                ImportAction::Invalid => counts.invalid += 1,
+            }
+            items.push(DryRunItem {
+                index: item.index,
+                slug: item.article.slug,
+                action,
+                error: item.outcome.err(),
+            });
+        }
+        DryRunResponse { counts, items }
+    }
+}
+
+/// Classifies each item of a `POST /articles/bulk` payload exactly as the
+/// import would (see `plan_import`), without writing anything.
+pub async fn import_dry_run(
+    State(state): State<AppState>,
+    Query(query): Query<WriteQuery>,
+    Json(articles): Json<Vec<Article>>,
+) -> Result<Json<DryRunResponse>, ApiError> {
+    check_batch_size(&articles, &state.config)?;
+    let stored = stored_for_import(&state, "import_dry_run", &articles).await?;
+    let plan = plan_import(articles, &stored, &query, &state.config);
+    Ok(Json(DryRunResponse::new(plan)))
+}
+
+/// Whether saving `incoming` over `stored` would change anything besides
+/// `updated_at`.
+fn same_content(stored: &Article, incoming: &Article) -> bool {
+    if stored.deleted_at.is_some() {
+        return false;
+    }
+    let mut stored = stored.clone();
+    let mut incoming = incoming.clone();
+    for article in [&mut stored, &mut incoming] {
+        article.id = None;
+        article.updated_at = None;
+    }
+    matches!(
+        (bson::to_document(&stored), bson::to_document(&incoming)),
+        (Ok(a), Ok(b)) if a == b
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KeywordCampaign {
+    #[serde(flatten)]
+    filter: BulkFilter,
+    keyword: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeywordCampaignResponse {
+    modified: u64,
+}
+
+/// Adds an SEO keyword to every article matching the filter. Articles that
+/// already have it, ignoring case as `normalize_article` does, or that are
+/// at `MAX_SEO_KEYWORDS` are left alone, so `modified` counts real
+/// additions.
+pub async fn add_keyword(
+    State(state): State<AppState>,
+    Json(request): Json<KeywordCampaign>,
+) -> Result<Json<KeywordCampaignResponse>, ApiError> {
+    let keyword = campaign_keyword(&request.keyword)?;
+    let mut filter = request.filter.to_filter()?;
+    filter.insert("seo_keywords", doc! { "$not": same_keyword(&keyword) });
+    filter.insert(
+        format!("seo_keywords.{}", MAX_SEO_KEYWORDS - 1),
+        doc! { "$exists": false },
+    );
+    // A pipeline rather than `$addToSet`, which fails on the `null` stored
+    // for articles without keywords.
+    let update = vec![doc! { "$set": {
+        "seo_keywords": { "$concatArrays": [
+            { "$ifNull": ["$seo_keywords", []] },
+            [&keyword],
+        ] },
+        "updated_at": now_timestamp(),
+    } }];
+    update_keywords(&state, "add_keyword", filter, update).await
+}
+
+/// Removes an SEO keyword, ignoring case, from every article matching the
+/// filter.
+pub async fn remove_keyword(
+    State(state): State<AppState>,
+    Json(request): Json<KeywordCampaign>,
+) -> Result<Json<KeywordCampaignResponse>, ApiError> {
+    let keyword = campaign_keyword(&request.keyword)?;
+    let mut filter = request.filter.to_filter()?;
+    filter.insert("seo_keywords", same_keyword(&keyword));
+    let update = doc! {
+        "$pull": { "seo_keywords": same_keyword(&keyword) },
+        "$set": { "updated_at": now_timestamp() },
+    };
+    update_keywords(&state, "remove_keyword", filter, update).await
+}
+
+/// The trimmed keyword, held to the same rules as `validate_article`.
+fn campaign_keyword(keyword: &str) -> Result<String, ApiError> {
+    let keyword = keyword.trim();
+    let len = keyword.chars().count();
+    if len == 0 || len > MAX_SEO_KEYWORD_CHARS {
+        return Err(validation_error(vec![FieldError {
+            field: "keyword",
+            message: format!("must be 1 to {} characters", MAX_SEO_KEYWORD_CHARS),
+        }]));
+    }
+    Ok(keyword.to_string())
+}
+
+/// Matches a stored keyword equal to `keyword` ignoring case.
+fn same_keyword(keyword: &str) -> Regex {
+    Regex {
+        pattern: format!("^{}$", escape_regex(keyword)),
+        options: "i".to_string(),
+    }
+}
+
+async fn update_keywords(
+    state: &AppState,
+    route: &'static str,
+    filter: Document,
+    update: impl Into<UpdateModifications>,
+) -> Result<Json<KeywordCampaignResponse>, ApiError> {
+    let result = timed(
+        state,
+        route,
+        &filter,
+        state.collection.update_many(filter.clone(), update),
+    )
+    .await
+    .map_err(internal_error)?;
+    tracing::info!(route, modified = result.modified_count, filter = %filter, "Keyword campaign");
+    state.list_cache.clear();
+    Ok(Json(KeywordCampaignResponse {
+        modified: result.modified_count,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(fields: serde_json::Value) -> Article {
+        let mut article = serde_json::json!({
+            "slug": "mon-article",
+            "titre": "Mon article",
+            "contenu": "x".repeat(200),
+        });
+        article
+            .as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(article).unwrap()
+    }
+
+    fn invalid_fields(fields: serde_json::Value) -> Vec<&'static str> {
+        let config = Config::from_lookup(|_| None).unwrap();
+        validate_article(&article(fields), &config)
+            .into_iter()
+            .map(|e| e.field)
+            .collect()
+    }
+
+    #[test]
+    fn validate_article_accepts_a_complete_article() {
+        assert!(invalid_fields(serde_json::json!({
+            "series": "rust-101",
+            "series_order": 0,
+            "date_publication": "2024-01-01T10:00:00+02:00",
+            "seo_keywords": ["rust"],
+            "related": ["autre-article"],
+            "tags": ["web"],
+        }))
+        .is_empty());
+    }
+
+    #[test]
+    fn validate_article_reports_every_invalid_field() {
+        let fields = invalid_fields(serde_json::json!({
+            "slug": "Mon Article",
+            "titre": "  ",
+            "series": "Rust 101",
+            "series_order": -1,
+            "date_publication": "demain",
+        }));
+        assert_eq!(
+            fields,
+            [
+                "slug",
+                "series",
+                "series_order",
+                "titre",
+                "date_publication"
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_article_limits_keywords_tags_and_related() {
+        let many = |prefix: &str, n: usize| -> Vec<String> {
+            (0..n).map(|i| format!("{}-{}", prefix, i)).collect()
+        };
+        let fields = invalid_fields(serde_json::json!({
+            "seo_keywords": many("k", MAX_SEO_KEYWORDS + 1),
+            "tags": many("t", MAX_TAGS + 1),
+            "related": many("r", MAX_RELATED + 1),
+        }));
+        assert_eq!(fields, ["seo_keywords", "related", "tags"]);
+
+        let fields = invalid_fields(serde_json::json!({
+            "seo_keywords": ["", "k".repeat(MAX_SEO_KEYWORD_CHARS + 1)],
+            "tags": ["t".repeat(MAX_TAG_CHARS + 1)],
+        }));
+        assert_eq!(fields, ["seo_keywords", "seo_keywords", "tags"]);
+    }
+
+    #[test]
+    fn validate_article_refuses_relating_to_itself() {
+        let fields = invalid_fields(serde_json::json!({
+            "related": ["mon-article", "Autre"],
+        }));
+        assert_eq!(fields, ["related", "related"]);
+    }
+
+    #[test]
+    fn validate_article_requires_content_unless_draft() {
+        // 225 characters, but only 162 of text once the markup is stripped.
+        let short = "<p>vingt caractères !</p>".repeat(9);
+        assert_eq!(
+            invalid_fields(serde_json::json!({ "contenu": short })),
+            ["contenu"]
+        );
+        assert_eq!(
+            invalid_fields(serde_json::json!({ "contenu": null, "status": "published" })),
+            ["contenu"]
+        );
+        assert!(
+            invalid_fields(serde_json::json!({ "contenu": null, "status": "draft" })).is_empty()
+        );
+    }
+
+    #[test]
+    fn derive_fields_normalizes_and_fills_seo() {
+        let config = Config::from_lookup(|_| None).unwrap();
+        let mut stored = article(serde_json::json!({
+            "tags": ["Web", "web "],
+            "related": [" Autre-Article"],
+            "seo_keywords": ["Rust", "rust"],
+        }));
+        derive_fields(&mut stored, &config);
+        assert_eq!(stored.tags, Some(vec!["web".to_string()]));
+        assert_eq!(stored.related, Some(vec!["autre-article".to_string()]));
+        assert_eq!(stored.seo_keywords, Some(vec!["Rust".to_string()]));
+        assert_eq!(stored.seo_title.as_deref(), Some("Mon article"));
+        assert!(stored.seo_description.is_some());
+    }
+
+    #[test]
+    fn derive_fields_keeps_the_auto_seo_opt_out() {
+        let config = Config::from_lookup(|_| None).unwrap();
+        let mut stored = article(serde_json::json!({ "tags": ["Web"] }));
+        let query = WriteQuery {
+            auto_seo: Some(false),
+        };
+        prepare(&mut stored, &query, &config).unwrap();
+        assert_eq!(stored.auto_seo, Some(false));
+        derive_fields(&mut stored, &config);
+        assert_eq!(stored.seo_title, None);
+        assert_eq!(stored.seo_description, None);
+
+        // A later save without the flag opts back in.
+        prepare(&mut stored, &WriteQuery { auto_seo: None }, &config).unwrap();
+        assert_eq!(stored.auto_seo, None);
+        assert!(stored.seo_title.is_some());
+    }
+
+    fn if_unmodified_since(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_UNMODIFIED_SINCE, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn unmodified_since_covers_the_named_second() {
+        let headers = if_unmodified_since("Mon, 01 Jan 2024 10:00:00 GMT");
+        assert_eq!(
+            unmodified_since(&headers).as_deref(),
+            Some("2024-01-01T10:00:01.000Z")
+        );
+        assert_eq!(unmodified_since(&HeaderMap::new()), None);
+        assert_eq!(unmodified_since(&if_unmodified_since("yesterday")), None);
+    }
+
+    #[test]
+    fn is_unmodified_compares_against_the_bound() {
+        let bound = "2024-01-01T10:00:01.000Z";
+        let updated = |at: &str| Some(at.to_string());
+        assert!(is_unmodified(&updated("2024-01-01T10:00:00.999Z"), bound));
+        assert!(!is_unmodified(&updated("2024-01-01T10:00:01.000Z"), bound));
+        assert!(!is_unmodified(&updated("2024-06-01T00:00:00.000Z"), bound));
+        // Stored before `updated_at` existed.
+        assert!(is_unmodified(&None, bound));
+    }
+
+    #[test]
+    fn check_unmodified_accepts_an_untouched_article() {
+        let stored = article(serde_json::json!({ "updated_at": "2024-01-01T09:59:59.000Z" }));
+        let headers = if_unmodified_since("Mon, 01 Jan 2024 10:00:00 GMT");
+        assert!(check_unmodified(&headers, &stored).is_ok());
+        // Without the header, writes are unconditional.
+        assert!(check_unmodified(&HeaderMap::new(), &stored).is_ok());
+    }
+
+    #[test]
+    fn check_unmodified_refuses_a_later_write() {
+        let stored = article(serde_json::json!({ "updated_at": "2024-01-01T10:00:05.000Z" }));
+        let headers = if_unmodified_since("Mon, 01 Jan 2024 10:00:00 GMT");
+        let (status, Json(error)) = check_unmodified(&headers, &stored).unwrap_err();
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        assert!(error.error.contains("mon-article"));
+    }
+
+    #[test]
+    fn unknown_slug_is_not_found() {
+        let (status, _) = found("inconnu", None).unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let stored = article(serde_json::json!({}));
+        assert_eq!(
+            found("mon-article", Some(stored)).unwrap().slug,
+            "mon-article"
+        );
+    }
+
+    #[test]
+    fn replace_matching_nothing_is_a_precondition_failure() {
+        let stored = article(serde_json::json!({}));
+        let (status, _) = replaced(&stored, 0).unwrap_err();
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        assert!(replaced(&stored, 1).is_ok());
+    }
+
+    #[test]
+    fn same_keyword_matches_the_whole_keyword_ignoring_case() {
+        let regex = same_keyword("C++");
+        assert_eq!(regex.pattern, r"^C\+\+$");
+        assert_eq!(regex.options, "i");
+    }
+
+    #[test]
+    fn dry_run_predicts_what_the_import_does() {
+        let config = Config::from_lookup(|_| None).unwrap();
+        let query = WriteQuery { auto_seo: None };
+        let mut same = article(serde_json::json!({ "slug": "identique" }));
+        prepare(&mut same, &query, &config).unwrap();
+        let mut deleted = same.clone();
+        deleted.slug = "supprime".into();
+        deleted.deleted_at = Some(now_timestamp());
+        let mut edited = same.clone();
+        edited.slug = "modifie".into();
+        edited.titre = "Ancien titre".into();
+        edited.sort_weight = Some(3);
+        let stored: HashMap<String, Article> = [same, deleted, edited]
+            .into_iter()
+            .map(|a| (a.slug.clone(), a))
+            .collect();
+        let payload = || {
+            vec![
+                article(serde_json::json!({ "slug": "nouveau" })),
+                article(serde_json::json!({ "slug": " identique " })),
+                article(serde_json::json!({ "slug": "supprime" })),
+                article(serde_json::json!({ "slug": "modifie" })),
+                article(serde_json::json!({ "slug": "Pas Valide" })),
+                article(serde_json::json!({ "slug": "nouveau" })),
+            ]
+        };
+
+        let dry_run = DryRunResponse::new(plan_import(payload(), &stored, &query, &config));
+        let actions: Vec<ImportAction> = dry_run.items.iter().map(|i| i.action).collect();
+        assert_eq!(
+            actions,
+            [
+                ImportAction::Create,
+                ImportAction::Unchanged,
+                ImportAction::Update,
+                ImportAction::Update,
+                ImportAction::Invalid,
+                ImportAction::Invalid,
+            ]
+        );
+
+        // `bulk_create` writes exactly what the dry run reported.
+        let plan = plan_import(payload(), &stored, &query, &config);
+        let slugs = |action| -> Vec<&str> {
+            plan.iter()
+                .filter(|i| i.action() == action)
+                .map(|i| i.article.slug.as_str())
+                .collect()
+        };
+        assert_eq!(slugs(ImportAction::Create), ["nouveau"]);
+        assert_eq!(slugs(ImportAction::Update), ["supprime", "modifie"]);
+        assert_eq!(slugs(ImportAction::Unchanged), ["identique"]);
+        let invalid = plan.iter().filter(|i| i.outcome.is_err()).count();
+        assert_eq!(invalid, dry_run.counts.invalid);
+        assert_eq!(dry_run.counts.create, 1);
+        assert_eq!(dry_run.counts.update, 2);
+        assert_eq!(dry_run.counts.unchanged, 1);
+
+        // Updates replace the stored article and keep its weight.
+        let update = plan.iter().find(|i| i.article.slug == "modifie").unwrap();
+        assert_eq!(update.stored.as_ref().unwrap().titre, "Ancien titre");
+        assert_eq!(update.article.sort_weight, Some(3));
+    }
+}